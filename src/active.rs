@@ -0,0 +1,127 @@
+use std::ops::Range;
+
+use crate::core::{self, Cells};
+use crate::rules::Rule;
+
+// Most of a settled board never changes, and a cell can only change if
+// something in its neighbourhood changed the generation before. So the board
+// is split into tiles, and only the tiles near the last changes are stepped.
+// The changes are picked up while stepping, the cells changed by hand in
+// between have to be passed to `edited()` (or the whole board to `reset()`).
+
+// Cells across (and down) a tile
+pub const TILE_SIZE: usize = 8;
+
+// Data structures
+// ----------------------------------------------------------------------------
+
+/// Which tiles of the board can change in the next generation.
+#[derive(Debug, Clone, Default)]
+pub struct ActiveRegion {
+    num_cells_x: usize,
+    num_cells_y: usize,
+    tiles_x: usize,
+    tiles_y: usize,
+    active: Vec<bool>,
+    // How far the neighbourhood of the rule we stepped with last reaches
+    reach: usize,
+    // Until the first generation (and after a reset) every tile is active
+    started: bool,
+    // How many cells the last generation looked at the neighbours of
+    pub evaluated: usize,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+impl ActiveRegion {
+    pub fn new() -> Self {
+        ActiveRegion::default()
+    }
+
+    /// Step every tile again in the next generation (eg. after a new board was loaded).
+    pub fn reset(&mut self) {
+        self.started = false;
+    }
+
+    /// The cell was changed by hand (not by stepping), so what's around it can change too.
+    pub fn edited(&mut self, x: usize, y: usize) {
+        if self.started && x < self.num_cells_x && y < self.num_cells_y {
+            self.activate_around(x, y, self.reach);
+        }
+    }
+
+    /// Whether the cell is in a tile that may change.
+    pub fn is_active(&self, x: usize, y: usize) -> bool {
+        self.active[(x / TILE_SIZE) * self.tiles_y + y / TILE_SIZE]
+    }
+
+    /// The cells of every active tile, as the ranges across and down it.
+    pub fn active_tiles(&self) -> impl Iterator<Item = (Range<usize>, Range<usize>)> + '_ {
+        // The tiles on the right and bottom edges can be cut short
+        (0..self.tiles_x * self.tiles_y)
+            .filter(|tile| self.active[*tile])
+            .map(|tile| {
+                let (x, y) = (
+                    (tile / self.tiles_y) * TILE_SIZE,
+                    (tile % self.tiles_y) * TILE_SIZE,
+                );
+                (
+                    x..(x + TILE_SIZE).min(self.num_cells_x),
+                    y..(y + TILE_SIZE).min(self.num_cells_y),
+                )
+            })
+    }
+
+    // Make the tiles of every cell within `reach` of the given one active
+    fn activate_around(&mut self, x: usize, y: usize, reach: usize) {
+        let x_tiles =
+            x.saturating_sub(reach) / TILE_SIZE..=((x + reach) / TILE_SIZE).min(self.tiles_x - 1);
+        for tile_x in x_tiles {
+            let y_tiles = y.saturating_sub(reach) / TILE_SIZE
+                ..=((y + reach) / TILE_SIZE).min(self.tiles_y - 1);
+            for tile_y in y_tiles {
+                self.active[tile_x * self.tiles_y + tile_y] = true;
+            }
+        }
+    }
+
+    /// Same as `core::step_cells_in_parallel()`, but only looking at the cells
+    /// near the ones that changed in the previous generation (or were edited since).
+    pub fn step(
+        &mut self,
+        cells: &Cells,
+        num_cells_x: usize,
+        num_cells_y: usize,
+        rule: &Rule,
+        threads: usize,
+    ) -> Cells {
+        let reach = rule.neighbourhood.reach();
+        let resized = (num_cells_x, num_cells_y) != (self.num_cells_x, self.num_cells_y);
+
+        if resized || !self.started || reach != self.reach || rule.elementary.is_some() {
+            // Elementary rules scroll the whole board every generation
+            self.num_cells_x = num_cells_x;
+            self.num_cells_y = num_cells_y;
+            self.tiles_x = num_cells_x.div_ceil(TILE_SIZE);
+            self.tiles_y = num_cells_y.div_ceil(TILE_SIZE);
+            self.active = vec![true; self.tiles_x * self.tiles_y];
+            self.reach = reach;
+            self.started = true;
+        }
+
+        self.evaluated = self
+            .active_tiles()
+            .map(|(xs, ys)| xs.len() * ys.len())
+            .sum();
+
+        let (next, changes) =
+            core::step_bands(cells, num_cells_x, num_cells_y, rule, threads, Some(self));
+
+        self.active.iter_mut().for_each(|a| *a = false);
+        for index in changes {
+            self.activate_around(index.x, index.y, reach);
+        }
+
+        next
+    }
+}
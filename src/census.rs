@@ -0,0 +1,432 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::core::{self, Cells};
+use crate::error::Error;
+use crate::rules::Rule;
+
+pub type Point = (i32, i32);
+
+// Data structures
+// ----------------------------------------------------------------------------
+pub struct KnownObject {
+    pub name: &'static str,
+    pub apgcode: &'static str,
+    pub period: usize,
+    // Rows separated by '/', 'o' is alive and '.' is dead
+    pub picture: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CensusEntry {
+    pub name: String,
+    // Only for the known objects, the others are told apart by their name
+    pub apgcode: Option<String>,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Census {
+    pub soups: usize,
+    pub entries: Vec<CensusEntry>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CensusFormat {
+    Text,
+    Json,
+}
+
+pub const KNOWN_OBJECTS: [KnownObject; 14] = [
+    KnownObject {
+        name: "block",
+        apgcode: "xs4_33",
+        period: 1,
+        picture: "oo/oo",
+    },
+    KnownObject {
+        name: "tub",
+        apgcode: "xs4_252",
+        period: 1,
+        picture: ".o./o.o/.o.",
+    },
+    KnownObject {
+        name: "boat",
+        apgcode: "xs5_253",
+        period: 1,
+        picture: "oo./o.o/.o.",
+    },
+    KnownObject {
+        name: "beehive",
+        apgcode: "xs6_696",
+        period: 1,
+        picture: ".oo./o..o/.oo.",
+    },
+    KnownObject {
+        name: "ship",
+        apgcode: "xs6_356",
+        period: 1,
+        picture: "oo./o.o/.oo",
+    },
+    KnownObject {
+        name: "barge",
+        apgcode: "xs6_25a4",
+        period: 1,
+        picture: ".o../o.o./.o.o/..o.",
+    },
+    KnownObject {
+        name: "loaf",
+        apgcode: "xs7_2596",
+        period: 1,
+        picture: ".oo./o..o/.o.o/..o.",
+    },
+    KnownObject {
+        name: "long boat",
+        apgcode: "xs7_25ac",
+        period: 1,
+        picture: "oo../o.o./.o.o/..o.",
+    },
+    KnownObject {
+        name: "pond",
+        apgcode: "xs8_6996",
+        period: 1,
+        picture: ".oo./o..o/o..o/.oo.",
+    },
+    KnownObject {
+        name: "blinker",
+        apgcode: "xp2_7",
+        period: 2,
+        picture: "ooo",
+    },
+    KnownObject {
+        name: "toad",
+        apgcode: "xp2_7e",
+        period: 2,
+        picture: ".ooo/ooo.",
+    },
+    KnownObject {
+        name: "beacon",
+        apgcode: "xp2_318c",
+        period: 2,
+        picture: "oo../oo../..oo/..oo",
+    },
+    KnownObject {
+        name: "glider",
+        apgcode: "xq4_153",
+        period: 4,
+        picture: ".o./..o/ooo",
+    },
+    KnownObject {
+        name: "lightweight spaceship",
+        apgcode: "xq4_6frc",
+        period: 4,
+        picture: ".o..o/o..../o...o/oooo.",
+    },
+];
+
+// Functions
+// ----------------------------------------------------------------------------
+pub fn parse_picture(picture: &str) -> Vec<Point> {
+    let mut points = Vec::new();
+
+    for (y, row) in picture.split('/').enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            if c == 'o' {
+                points.push((x as i32, y as i32));
+            }
+        }
+    }
+
+    points
+}
+
+/// Find the groups of live cells that touch each other (including diagonally).
+pub fn connected_components(points: &[Point]) -> Vec<Vec<Point>> {
+    components_within(points, 1)
+}
+
+/// Same, but cells up to `distance` apart (across or down) are in the same group.
+pub fn components_within(points: &[Point], distance: i32) -> Vec<Vec<Point>> {
+    let mut remaining: HashSet<Point> = points.iter().copied().collect();
+    let mut components = Vec::new();
+
+    // Iterate in the input order, so the output is deterministic
+    for start in points.iter() {
+        if !remaining.remove(start) {
+            continue;
+        }
+
+        let mut component = vec![*start];
+        let mut stack = vec![*start];
+
+        while let Some((x, y)) = stack.pop() {
+            for dy in -distance..=distance {
+                for dx in -distance..=distance {
+                    let neighbour = (x + dx, y + dy);
+                    if remaining.remove(&neighbour) {
+                        component.push(neighbour);
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+
+        component.sort();
+        components.push(component);
+    }
+
+    components
+}
+
+pub fn live_points(cells: &Cells, num_cells_x: usize, num_cells_y: usize) -> Vec<Point> {
+    let mut points = Vec::new();
+
+    for (x, row) in cells.rows.iter().enumerate().take(num_cells_x) {
+        for (y, cell) in row.values.iter().enumerate().take(num_cells_y) {
+            if cell.is_alive() {
+                points.push((x as i32, y as i32));
+            }
+        }
+    }
+
+    points
+}
+
+/// Translate the points so that their bounding box starts at 0,0, sorted.
+pub fn normalise(points: &[Point]) -> Vec<Point> {
+    let min_x = points.iter().map(|p| p.0).min().unwrap_or(0);
+    let min_y = points.iter().map(|p| p.1).min().unwrap_or(0);
+
+    let mut normalised: Vec<Point> = points.iter().map(|(x, y)| (x - min_x, y - min_y)).collect();
+    normalised.sort();
+
+    normalised
+}
+
+/// The same representation for an object no matter how it is
+/// positioned, rotated or reflected: the smallest of its 8 orientations.
+pub fn canonicalise(points: &[Point]) -> Vec<Point> {
+    let transforms: [fn(Point) -> Point; 8] = [
+        |(x, y)| (x, y),
+        |(x, y)| (-y, x),
+        |(x, y)| (-x, -y),
+        |(x, y)| (y, -x),
+        |(x, y)| (-x, y),
+        |(x, y)| (x, -y),
+        |(x, y)| (y, x),
+        |(x, y)| (-y, -x),
+    ];
+
+    transforms
+        .iter()
+        .map(|t| normalise(&points.iter().map(|p| t(*p)).collect::<Vec<Point>>()))
+        .min()
+        .unwrap_or_default()
+}
+
+/// One generation of B3/S23 on an unbounded plane, used to
+/// work out all of the phases of the known objects.
+pub fn step_points(points: &[Point]) -> Vec<Point> {
+    let alive: HashSet<Point> = points.iter().copied().collect();
+    let mut counts: HashMap<Point, usize> = HashMap::new();
+
+    for (x, y) in points.iter() {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if dx != 0 || dy != 0 {
+                    *counts.entry((x + dx, y + dy)).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    let mut next: Vec<Point> = counts
+        .into_iter()
+        .filter(|(p, n)| *n == 3 || (*n == 2 && alive.contains(p)))
+        .map(|(p, _n)| p)
+        .collect();
+    next.sort();
+
+    next
+}
+
+/// Map from the canonical form of every phase of every known object to its entry.
+pub fn known_object_table() -> HashMap<Vec<Point>, &'static KnownObject> {
+    let mut table = HashMap::new();
+
+    for object in KNOWN_OBJECTS.iter() {
+        let mut phase = parse_picture(object.picture);
+
+        for _ in 0..object.period {
+            table.insert(canonicalise(&phase), object);
+            phase = step_points(&phase);
+        }
+    }
+
+    table
+}
+
+/// Classify all of the objects on a board which has stabilised with the given period.
+/// The known objects are the ones of B3/S23, under other rules most will be unidentified.
+///
+/// Oscillators can fall apart into separate pieces in some of their phases
+/// (eg: the beacon), so the objects are found in the union of all the
+/// phases over a full period, then identified from the current phase.
+pub fn take_census(
+    cells: &Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    period: usize,
+    rule: &Rule,
+) -> Census {
+    let current = live_points(cells, num_cells_x, num_cells_y);
+    let current_set: HashSet<Point> = current.iter().copied().collect();
+
+    let mut union: HashSet<Point> = current_set.clone();
+    let mut phase = cells.clone();
+    for _ in 1..period.max(1) {
+        phase = core::step_cells(&phase, num_cells_x, num_cells_y, rule);
+        union.extend(live_points(&phase, num_cells_x, num_cells_y));
+    }
+
+    let mut union: Vec<Point> = union.into_iter().collect();
+    union.sort();
+
+    let table = known_object_table();
+    let mut census = Census {
+        soups: 1,
+        entries: Vec::new(),
+    };
+
+    for region in connected_components(&union) {
+        let object: Vec<Point> = region
+            .into_iter()
+            .filter(|p| current_set.contains(p))
+            .collect();
+        if object.is_empty() {
+            continue;
+        }
+
+        match table.get(&canonicalise(&object)) {
+            Some(known) => census.add_object(known.name, Some(known.apgcode), 1),
+            // Not in the table: at least say how big it is. Not an apgcode, those
+            // have to be worked out from the object itself, every phase of it
+            None => census.add_object(&format!("unknown({} cells)", object.len()), None, 1),
+        }
+    }
+
+    census.sort();
+    census
+}
+
+impl Census {
+    pub fn new() -> Self {
+        Census::default()
+    }
+
+    pub fn add_object(&mut self, name: &str, apgcode: Option<&str>, count: usize) {
+        let same = |e: &&mut CensusEntry| match apgcode {
+            Some(apgcode) => e.apgcode.as_deref() == Some(apgcode),
+            None => e.apgcode.is_none() && e.name == name,
+        };
+
+        match self.entries.iter_mut().find(same) {
+            Some(entry) => entry.count += count,
+            None => self.entries.push(CensusEntry {
+                name: name.to_string(),
+                apgcode: apgcode.map(String::from),
+                count,
+            }),
+        }
+    }
+
+    /// Add up the results of another census (eg: of another soup).
+    pub fn merge(&mut self, other: &Census) {
+        self.soups += other.soups;
+        for entry in other.entries.iter() {
+            self.add_object(&entry.name, entry.apgcode.as_deref(), entry.count);
+        }
+        self.sort();
+    }
+
+    /// Most common objects first, like apgsearch does (the unknown ones last).
+    pub fn sort(&mut self) {
+        self.entries.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(a.apgcode.is_none().cmp(&b.apgcode.is_none()))
+                .then(a.apgcode.cmp(&b.apgcode))
+                .then(a.name.cmp(&b.name))
+        });
+    }
+
+    pub fn to_text(&self) -> String {
+        let total: usize = self.entries.iter().map(|e| e.count).sum();
+        let mut text = format!("Census of {} soup(s), {total} object(s):\n", self.soups);
+
+        for entry in self.entries.iter() {
+            text.push_str(&format!(
+                "{:<16} {:<24} {:>8}\n",
+                entry.apgcode.as_deref().unwrap_or("-"),
+                entry.name,
+                entry.count
+            ));
+        }
+
+        text
+    }
+
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self
+            .entries
+            .iter()
+            .map(|e| {
+                let apgcode = match &e.apgcode {
+                    Some(apgcode) => format!("\"{apgcode}\""),
+                    None => String::from("null"),
+                };
+                format!(
+                    "    {{\"apgcode\": {apgcode}, \"name\": \"{}\", \"count\": {}}}",
+                    e.name, e.count
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\n  \"soups\": {},\n  \"census\": [\n{}\n  ]\n}}\n",
+            self.soups,
+            entries.join(",\n")
+        )
+    }
+
+    pub fn format(&self, format: CensusFormat) -> String {
+        match format {
+            CensusFormat::Text => self.to_text(),
+            CensusFormat::Json => self.to_json(),
+        }
+    }
+}
+
+impl fmt::Display for CensusFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CensusFormat::Text => write!(f, "text"),
+            CensusFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for CensusFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(CensusFormat::Text),
+            "json" => Ok(CensusFormat::Json),
+            _ => Err(Error::Invalid(format!(
+                "Unknown census format '{s}', expected text or json"
+            ))),
+        }
+    }
+}
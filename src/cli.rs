@@ -0,0 +1,235 @@
+use log::LevelFilter;
+
+use crate::census::CensusFormat;
+use crate::core;
+use crate::detect::StableAction;
+use crate::elementary::{Boundary, InitialRow};
+use crate::error::Error;
+use crate::geometry::Geometry;
+use crate::keymap::{self, Keymap};
+use crate::logging;
+use crate::neighbourhood::{self, Neighbourhood};
+use crate::rle::Pattern;
+use crate::rules::Rule;
+use crate::theme::{self, Theme};
+
+pub const DEFAULT_MAX_GENERATIONS: i64 = 100_000;
+
+pub const USAGE: &str = "\
+Usage: game-of-life [OPTIONS]
+
+Options:
+  --headless               Run a random soup without opening a window
+  --max-generations <N>    Give up on a headless run after N generations
+  --soups <N>              Number of random soups to run headless (default: 1)
+  --census <FORMAT>        Classify the objects left on the settled board(s)
+                           and print a census as text or json
+  --track                  Report the gliders and spaceships seen in the soup(s)
+  --rule <RULE>            Rulestring (B3/S23, B2/S/C3, B2n3/S23-q..) or one of:
+                           life, highlife, seeds, day-and-night,
+                           brians-brain, star-wars, tlife, bosco, majority,
+                           hex-life, wireworld, rule-30, rule-110 (default:
+                           life). Larger than Life rules are written as
+                           R5,C0,M1,S34..58,B34..45,NM, elementary 1D rules
+                           as W0 to W255. Anything else is read from a
+                           Golly .rule file
+  --geometry <GEOMETRY>    Cells shape: square, hex or triangle, with
+                           the cells they touch as neighbours (hex rules
+                           can also be given as B2/S34H, triangle ones
+                           as B4/S3,4,5T)
+  --neighbourhood <FILE>   Count the neighbours in a custom shape, drawn
+                           in a text file with 'x' for the neighbours
+  --row <ROW>              First row of an elementary rule: seed, random,
+                           or cells like '..o.oo' (default: seed)
+  --boundary <BOUNDARY>    Cells past the ends of the row of an elementary
+                           rule: dead, alive or wrap (default: dead)
+  --size <WIDTH>x<HEIGHT>  Number of cells across and down the board
+                           (default: 128x128)
+  --pattern <FILE>         Start from an RLE pattern instead of a random soup
+  --infinite               Run on an unbounded plane instead of the fixed
+                           size board (arrow keys and +/- move the view)
+  --theme <THEME>          Colour theme: classic, light, high-contrast,
+                           colour-blind, or the path to a .toml theme file
+  --keys <FILE>            Rebind the keys from a .toml keymap file
+  --no-hud                 Start without the HUD, messages (but warnings) and key help
+                           (for clean recordings, 'H' brings them back)
+  --threads <N>            Step the board on N threads at once
+                           (default: one per core)
+  --on-stable <ACTION>     What to do once the board stabilises:
+                           continue, pause or reset (default: continue)
+  --log <FILTERS>          What to log (on stderr), eg. 'warn' or
+                           'simulation=debug,input=trace', for the
+                           input, simulation, io and render targets
+  -v, --verbose            Log more (debug, then trace with -vv)
+  -q, --quiet              Log less (only warnings, then only errors),
+                           eg. for headless runs that only need the results
+  -h, --help               Print this help";
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub headless: bool,
+    pub max_generations: i64,
+    pub on_stable: StableAction,
+    pub soups: usize,
+    pub census: Option<CensusFormat>,
+    pub track: bool,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    pub rule: Option<Rule>,
+    pub geometry: Option<Geometry>,
+    pub neighbourhood: Option<Neighbourhood>,
+    pub initial_row: InitialRow,
+    pub boundary: Option<Boundary>,
+    // Cells across and down the board
+    pub size: (usize, usize),
+    pub pattern: Option<String>,
+    pub infinite: bool,
+    // Nothing on top of the board, eg. for recordings
+    pub no_hud: bool,
+    pub threads: usize,
+    pub log_level: LevelFilter,
+    pub log_filters: Option<String>,
+    pub show_help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            headless: false,
+            max_generations: DEFAULT_MAX_GENERATIONS,
+            on_stable: StableAction::Continue,
+            soups: 1,
+            census: None,
+            track: false,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            rule: None,
+            geometry: None,
+            neighbourhood: None,
+            initial_row: InitialRow::default(),
+            boundary: None,
+            size: (core::DEFAULT_NUM_CELLS_X, core::DEFAULT_NUM_CELLS_Y),
+            pattern: None,
+            infinite: false,
+            no_hud: false,
+            threads: core::default_threads(),
+            log_level: logging::level_for(0),
+            log_filters: None,
+            show_help: false,
+        }
+    }
+}
+
+fn next_value<I: Iterator<Item = String>>(args: &mut I, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("Missing value for '{flag}'"))
+}
+
+// A board size like 320x180
+fn parse_size(value: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("Invalid board size '{value}' (expected eg. 320x180)");
+    let (width, height) = value.split_once(['x', 'X']).ok_or_else(invalid)?;
+
+    match (width.trim().parse(), height.trim().parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse the command line arguments (without the program name).
+pub fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, Error> {
+    let mut options = Options::default();
+    let mut verbosity = 0;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--headless" => options.headless = true,
+            "--max-generations" => {
+                let value = next_value(&mut args, &arg)?;
+                options.max_generations = value
+                    .parse()
+                    .map_err(|_| format!("Invalid number of generations '{value}'"))?;
+            }
+            "--on-stable" => {
+                options.on_stable = next_value(&mut args, &arg)?.parse()?;
+            }
+            "--soups" => {
+                let value = next_value(&mut args, &arg)?;
+                options.soups = value
+                    .parse()
+                    .map_err(|_| format!("Invalid number of soups '{value}'"))?;
+            }
+            "--census" => {
+                options.census = Some(next_value(&mut args, &arg)?.parse()?);
+            }
+            "--track" => options.track = true,
+            "--rule" => options.rule = Some(next_value(&mut args, &arg)?.parse()?),
+            "--geometry" => options.geometry = Some(next_value(&mut args, &arg)?.parse()?),
+            "--neighbourhood" => {
+                options.neighbourhood =
+                    Some(neighbourhood::load_mask(next_value(&mut args, &arg)?)?);
+            }
+            "--row" => options.initial_row = next_value(&mut args, &arg)?.parse()?,
+            "--boundary" => options.boundary = Some(next_value(&mut args, &arg)?.parse()?),
+            "--size" => options.size = parse_size(&next_value(&mut args, &arg)?)?,
+            "--pattern" => options.pattern = Some(next_value(&mut args, &arg)?),
+            "--infinite" => options.infinite = true,
+            "--no-hud" => options.no_hud = true,
+            "--threads" => {
+                let value = next_value(&mut args, &arg)?;
+                options.threads = match value.parse() {
+                    Ok(threads) if threads > 0 => threads,
+                    _ => {
+                        return Err(Error::Invalid(format!(
+                            "Invalid number of threads '{value}'"
+                        )))
+                    }
+                };
+            }
+            "--theme" => {
+                options.theme = theme::theme_from_arg(&next_value(&mut args, &arg)?)?;
+            }
+            "--keys" => options.keymap = keymap::load_keymap(&next_value(&mut args, &arg)?)?,
+            "--log" => options.log_filters = Some(next_value(&mut args, &arg)?),
+            "-v" | "--verbose" => verbosity += 1,
+            "-vv" => verbosity += 2,
+            "-q" | "--quiet" => verbosity -= 1,
+            "-h" | "--help" => options.show_help = true,
+            _ => return Err(Error::Invalid(format!("Unknown argument '{arg}'"))),
+        }
+    }
+
+    options.log_level = logging::level_for(verbosity);
+
+    Ok(options)
+}
+
+pub fn options_from_env() -> Result<Options, Error> {
+    parse_args(std::env::args().skip(1))
+}
+
+impl Options {
+    /// The rule given on the command line, or else the one in the pattern file,
+    /// counting the neighbours with `--geometry` or `--neighbourhood` if given
+    /// (or with the `--boundary` for elementary rules).
+    pub fn rule_for(&self, pattern: Option<&Pattern>) -> Rule {
+        let mut rule = self
+            .rule
+            .clone()
+            .or_else(|| pattern.and_then(|p| p.rule.clone()))
+            .unwrap_or_default();
+
+        if let (Some(elementary), Some(boundary)) = (&mut rule.elementary, self.boundary) {
+            elementary.boundary = boundary;
+        }
+
+        if let Some(geometry) = self.geometry {
+            rule = rule.with_neighbourhood(geometry.neighbourhood());
+        }
+
+        match &self.neighbourhood {
+            Some(neighbourhood) => rule.with_neighbourhood(neighbourhood.clone()),
+            None => rule,
+        }
+    }
+}
@@ -1,15 +1,57 @@
+use std::collections::HashMap;
+use std::thread;
+
+use log::Level;
 use nannou::prelude::*;
 use rand::Rng;
 
-// NB: Don't let it grow too big or you'll get stack overflows at compile time :)
-pub const GRID_SIZE: usize = 128;
+use crate::active::{ActiveRegion, TILE_SIZE};
+use crate::cursor::Cursor;
+use crate::detect::{CycleDetector, StabilisationReport, StableAction};
+use crate::elementary::{self, Elementary, InitialRow};
+use crate::geometry::{Geometry, Layout};
+use crate::hensel;
+use crate::keymap::Keymap;
+use crate::neighbourhood;
+use crate::plane::{Camera, Plane};
+use crate::render;
+use crate::rules::{self, Rule};
+use crate::ruletable::RuleTable;
+use crate::stats::{self, StatsHistory};
+use crate::theme::Theme;
+use crate::tracking::Tracker;
+
+// The board when there's no window to fit it in (eg. headless runs)
+pub const DEFAULT_NUM_CELLS_X: usize = 128;
+pub const DEFAULT_NUM_CELLS_Y: usize = 128;
 
 pub const GRID_LINE_WEIGHT: f32 = 0.3;
 pub const CELL_SIZE: usize = 4;
 
+// How many generations the population plot shows at once
+pub const PLOT_HISTORY: usize = 200;
+pub const PLOT_WIDTH: f32 = 160.0;
+pub const PLOT_HEIGHT: f32 = 48.0;
+
+// How long what just happened stays on screen, and how many things at once
+pub const MESSAGE_SECONDS: f32 = 3.0;
+pub const MAX_MESSAGES: usize = 4;
+
+// Where the 8 neighbours of a cell are, from the top-left one to the bottom-right one
+pub const MOORE_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
 // Data structures
 // ----------------------------------------------------------------------------
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct CellIndex {
     pub x: usize,
     pub y: usize,
@@ -17,22 +59,67 @@ pub struct CellIndex {
 
 #[derive(Debug, Copy, Clone)]
 pub struct Cell {
-    pub is_alive: bool,
+    // See `rules`: 0 is dead, 1 is alive, anything above is dying
+    pub state: u8,
+    // Generations in a row the cell has been alive for (0 when dead)
+    pub age: u32,
+    // Generations since the cell last died (0 when alive, NEVER_DIED if it never did)
+    pub time_since_death: u32,
+    // How many times the cell was born or died
+    pub activity: u32,
 }
 
-#[derive(Debug, Copy, Clone)]
+pub const NEVER_DIED: u32 = u32::MAX;
+
+pub const DEAD_CELL: Cell = Cell {
+    state: rules::DEAD,
+    age: 0,
+    time_since_death: NEVER_DIED,
+    activity: 0,
+};
+
+pub const ALIVE_CELL: Cell = Cell {
+    state: rules::ALIVE,
+    age: 1,
+    time_since_death: 0,
+    activity: 0,
+};
+
+impl Cell {
+    pub fn is_alive(&self) -> bool {
+        self.state == rules::ALIVE
+    }
+}
+
+// NB: a "row" is all the cells with the same x (so, a column on screen),
+// and its values go from the top to the bottom of the board
+#[derive(Debug, Clone)]
 pub struct CellsRow {
-    pub values: [Cell; GRID_SIZE],
+    pub values: Vec<Cell>,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct Cells {
-    pub rows: [CellsRow; GRID_SIZE],
+    pub rows: Vec<CellsRow>,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum AppState {
     Init,
     Running,
+    Paused,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RenderMode {
+    // Just the alive and dead colours of the theme
+    Alive,
+    // From yellow (newborn) to dark red (old)
+    Age,
+    // Newborn cells in green, recently dead ones fading out in red
+    BirthsDeaths,
+    // How often the cell has changed since the start
+    Activity,
 }
 
 pub enum DrawingState {
@@ -61,102 +148,136 @@ pub struct Model {
     pub state: AppState,
     pub should_draw_grid: bool,
     pub should_draw_white: bool,
+    // What the left mouse button draws, for rules with more than 2 states
+    pub draw_state: u8,
     pub drawing_state: DrawingState,
     pub current_stroke: Vec<Point2>,
     pub grid_points: Vec<Point2>,
     pub last_mouse_pos: Point2,
     pub closest_points: Vec<Point2>,
     pub generations: i64,
+    pub stats: StatsHistory,
+    pub should_draw_hud: bool,
+    pub last_generation_time: f32,
+    pub generations_per_second: f32,
+    pub detector: CycleDetector,
+    pub stabilisation: Option<StabilisationReport>,
+    pub on_stable: StableAction,
+    pub tracker: Tracker,
+    pub should_draw_trails: bool,
+    pub render_mode: RenderMode,
+    pub theme: Theme,
+    pub rule: Rule,
+    // Where the cells are on screen, for the geometry of the rule
+    pub layout: Layout,
+    // What elementary rules start from
+    pub initial_row: InitialRow,
+    // How many threads step the board
+    pub threads: usize,
+    // Where the board can still change, so the rest isn't stepped
+    pub active: ActiveRegion,
+    // Set when running on the infinite plane instead of `cells`
+    pub plane: Option<Plane>,
+    // What part of the infinite plane is in view
+    pub camera: Camera,
+    // Set while editing the board with the keyboard
+    pub cursor: Option<Cursor>,
+    pub pattern_path: String,
+    // Which keys do what
+    pub keymap: Keymap,
+    pub should_draw_help: bool,
+    // What just happened (saved, paused..), how bad it is and when it did
+    pub messages: Vec<(String, Level, f32)>,
 }
 
 // Functions
 // ----------------------------------------------------------------------------
-pub fn get_neighbours_indices(x: usize, y: usize, cells: Cells) -> Vec<CellIndex> {
-    let mut neighbours = Vec::new();
-    let rows = cells.rows;
-
-    // Top neighbours
-    if y > 0 {
-        let top_index = y - 1;
-        let top_row = rows.get(top_index).unwrap();
-
-        for i in -1..2 {
-            let index = x as i32 + i;
-            let neighbour = top_row.values.get(index as usize);
-            if let Some(_x) = neighbour {
-                neighbours.push(CellIndex {
-                    x: index as usize,
-                    y: top_index,
-                })
-            }
-        }
-    }
+/// The 8 cells around the given one on a board that doesn't wrap, so 5 along
+/// the edges and 3 in the corners. `x` goes across the board (the rows of
+/// `Cells`) and `y` down it (their values), same as everywhere else.
+pub fn neighbours(
+    x: usize,
+    y: usize,
+    num_cells_x: usize,
+    num_cells_y: usize,
+) -> impl Iterator<Item = CellIndex> {
+    MOORE_OFFSETS.iter().filter_map(move |(dx, dy)| {
+        let nx = x.checked_add_signed(*dx).filter(|nx| *nx < num_cells_x)?;
+        let ny = y.checked_add_signed(*dy).filter(|ny| *ny < num_cells_y)?;
+        Some(CellIndex { x: nx, y: ny })
+    })
+}
 
-    // Bottom neighbours
-    if y < rows.len() - 2 {
-        let bottom_index = y + 1;
-        let bottom_row = rows.get(bottom_index).unwrap();
+/// How many of the cells around the given one are alive (see `neighbours()`).
+pub fn alive_neighbours(
+    cells: &Cells,
+    x: usize,
+    y: usize,
+    num_cells_x: usize,
+    num_cells_y: usize,
+) -> usize {
+    neighbours(x, y, num_cells_x, num_cells_y)
+        .filter(|n| cells.rows[n.x].values[n.y].is_alive())
+        .count()
+}
 
-        for i in -1..2 {
-            let index = x as i32 + i;
-            let neighbour = bottom_row.values.get(index as usize);
-            if let Some(_x) = neighbour {
-                neighbours.push(CellIndex {
-                    x: index as usize,
-                    y: bottom_index,
-                })
-            }
-        }
-    }
+/// Same as `neighbours()`, on a board as big as `cells`.
+pub fn get_neighbours_indices(x: usize, y: usize, cells: &Cells) -> Vec<CellIndex> {
+    let num_cells_x = cells.rows.len();
+    let num_cells_y = cells.rows.first().map_or(0, |row| row.values.len());
 
-    // Left and right neighbours
-    let c = rows.get(y);
-    if let Some(central_row) = c {
-        // Left
-        let l_index = x - 1;
-        let l_neighbour = central_row.values.get(l_index);
-        if let Some(_l) = l_neighbour {
-            neighbours.push(CellIndex { x: l_index, y })
-        }
+    neighbours(x, y, num_cells_x, num_cells_y).collect()
+}
+
+/// How many cells of the given size fit in the window, across and down.
+pub fn board_size(app_width: f32, app_height: f32, cell_size: usize) -> (usize, usize) {
+    let fit = |length: f32| ((length as usize) / cell_size).max(1);
+    (fit(app_width), fit(app_height))
+}
+
+/// The points in between the cells, which the mouse snaps to.
+pub fn create_grid_points(app_width: f32, app_height: f32, step_size: usize) -> Vec<Point2> {
+    let w = app_width as i32;
+    let h = app_height as i32;
 
-        // Right
-        let r_index = x + 1;
-        let r_neighbour = central_row.values.get(x + 1);
-        if let Some(_r) = r_neighbour {
-            neighbours.push(CellIndex { x: r_index, y })
+    let mut grid_points = Vec::new();
+    for y in (-h..h).step_by(step_size) {
+        for x in (-w..w).step_by(step_size) {
+            grid_points.push(pt2(x as f32, y as f32));
         }
     }
-
-    neighbours
+    grid_points
 }
 
-pub fn create_grid(app: &App, step_size: usize) -> Vec<Line> {
+/// The lines of a grid of squares, around every cell of the board.
+pub fn create_grid(layout: &Layout) -> Vec<Line> {
     let mut lines = Vec::new();
     let mut horizontal_lines = Vec::new();
     let mut vertical_lines = Vec::new();
 
-    let window = app.window_rect();
-    let width = window.w() as i32;
-    let height = window.w() as i32;
+    // The board keeps its size when the window changes, so this can go past
+    // the edges of the window or stop short of them
+    let (num_cells_x, num_cells_y) = (layout.num_cells_x, layout.num_cells_y);
+    let (cell_width, cell_height) = (layout.cell_width, layout.cell_height);
 
-    let start_w = -width / 2;
-    let end_w = width / 2;
+    let start_w = layout.left;
+    let end_w = start_w + num_cells_x as f32 * cell_width;
 
-    let start_h = -height / 2;
-    let end_h = -start_h;
+    let start_h = layout.top;
+    let end_h = start_h - num_cells_y as f32 * cell_height;
 
     // This is stored per-line so that one day this could procedural
     // and different (eg: every N line, make a thicker one..)
     let current_weight = GRID_LINE_WEIGHT;
 
     // Horizontal lines
-    for i in (start_h..end_h).step_by(step_size) {
-        let current_y = i as f32;
+    for i in 0..=num_cells_y {
+        let current_y = start_h - i as f32 * cell_height;
         //println!("Adding line at {}", current_y);
 
         let line_props = Line {
-            start_x: start_w as f32,
-            end_x: end_w as f32,
+            start_x: start_w,
+            end_x: end_w,
             start_y: current_y,
             end_y: current_y,
             weight: current_weight,
@@ -167,14 +288,14 @@ pub fn create_grid(app: &App, step_size: usize) -> Vec<Line> {
     }
 
     // Vertical lines
-    for j in (start_w..end_w).step_by(step_size) {
-        let current_x = j as f32;
+    for j in 0..=num_cells_x {
+        let current_x = start_w + j as f32 * cell_width;
 
         let line_props = Line {
             start_x: current_x,
             end_x: current_x,
-            start_y: start_h as f32,
-            end_y: end_h as f32,
+            start_y: start_h,
+            end_y: end_h,
             weight: current_weight,
         };
 
@@ -185,29 +306,357 @@ pub fn create_grid(app: &App, step_size: usize) -> Vec<Line> {
     lines
 }
 
-pub fn draw_cell(x: usize, y: usize, alive: &bool, model: &Model, canvas: &Draw) {
-    let size = model.cell_size as f32;
+/// The lines of the grid for the given layout.
+pub fn create_grid_for(layout: &Layout) -> Vec<Line> {
+    match layout.geometry {
+        Geometry::Square => create_grid(layout),
+        _ => layout.grid_lines(),
+    }
+}
+
+/// Draw every cell of the board that isn't the colour of the background, all at once.
+pub fn draw_cells(model: &Model, canvas: &Draw) {
+    let triangles = render::board_mesh(
+        &model.cells,
+        &model.layout,
+        model.render_mode,
+        &model.theme,
+        &model.rule,
+    );
+    render::draw_mesh(&triangles, canvas);
+}
+
+/// Draw the cells of the infinite plane in view of the camera (and the grid, if it's on).
+pub fn draw_plane(plane: &Plane, model: &Model, canvas: &Draw) {
+    let camera = &model.camera;
+    let size = camera.cell_size;
+    let (min_x, min_y, max_x, max_y) = camera.visible(model.app_width, model.app_height);
 
-    // Convert from the 0,0 top-left system that I'm used to
-    // (because I programmed in Processing/Openframeworks for ages),
-    // to the 0,0-is-center system that nannou uses (which is the same
-    // that OpenGL uses, afaik)
-    let x_scaled = x as f32 * size;
-    let y_scaled = y as f32 * size;
-    let real_x = x_scaled - (model.app_width * 0.5) + (size * 0.5);
-    let real_y = -y_scaled + (model.app_height * 0.5) - (size * 0.5);
+    let triangles = render::plane_mesh(
+        plane,
+        camera,
+        model.app_width,
+        model.app_height,
+        model.render_mode,
+        &model.theme,
+        &model.rule,
+    );
+    render::draw_mesh(&triangles, canvas);
 
-    let color = if !alive { BLACK } else { RED };
+    // Too zoomed out, it would just be a grid coloured rectangle
+    if !model.should_draw_grid || size < 4.0 {
+        return;
+    }
+
+    let (left, top) = (-model.app_width * 0.5, model.app_height * 0.5);
+    for x in min_x..=max_x + 1 {
+        let start = camera.to_screen(x, min_y);
+        canvas
+            .line()
+            .start(pt2(start.x, top))
+            .end(pt2(start.x, -top))
+            .weight(GRID_LINE_WEIGHT)
+            .color(model.theme.grid);
+    }
+    for y in min_y..=max_y + 1 {
+        let start = camera.to_screen(min_x, y);
+        canvas
+            .line()
+            .start(pt2(left, start.y))
+            .end(pt2(-left, start.y))
+            .weight(GRID_LINE_WEIGHT)
+            .color(model.theme.grid);
+    }
+}
+
+// Colours along the way from 0.0 to 1.0, blended linearly in between
+fn gradient(stops: &[(f32, [u8; 3])], t: f32) -> Rgb8 {
+    let t = t.clamp(0.0, 1.0);
+
+    for pair in stops.windows(2) {
+        let (t0, c0) = pair[0];
+        let (t1, c1) = pair[1];
+        if t <= t1 {
+            let f = (t - t0) / (t1 - t0);
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+            return rgb8(mix(c0[0], c1[0]), mix(c0[1], c1[1]), mix(c0[2], c1[2]));
+        }
+    }
+
+    let [r, g, b] = stops[stops.len() - 1].1;
+    rgb8(r, g, b)
+}
+
+fn channels(color: Rgb8) -> [u8; 3] {
+    [color.red, color.green, color.blue]
+}
+
+// The given colour, see-through, for things drawn on top of the cells
+pub fn translucent(color: Rgb8, alpha: f32) -> Rgba {
+    let [r, g, b] = channels(color);
+    rgba(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, alpha)
+}
+
+// Ages (and activity) grow quickly at first, so use a log scale up to these
+pub const AGE_SCALE: f32 = 128.0;
+pub const ACTIVITY_SCALE: f32 = 256.0;
+// How many generations a dead cell keeps fading out for
+pub const DEATH_FADE: u32 = 16;
+
+pub fn cell_color(cell: &Cell, mode: RenderMode, theme: &Theme, rule: &Rule) -> Rgb8 {
+    let dead = channels(theme.dead);
+    let states = rule.states;
+
+    // Rules from a .rule file can come with their own colours (except for
+    // the dead cells, which are left to the theme)
+    let table_color = rule
+        .table
+        .as_ref()
+        .filter(|_| cell.state != rules::DEAD)
+        .and_then(|table| table.colors.get(cell.state as usize).copied().flatten());
+
+    if let (RenderMode::Alive, Some([r, g, b])) = (mode, table_color) {
+        return rgb8(r, g, b);
+    }
+
+    match mode {
+        RenderMode::Alive => match cell.state {
+            rules::DEAD => theme.dead,
+            rules::ALIVE => theme.alive,
+            // Dying cells fade from the alive colour to the dead one
+            dying => {
+                let t = (dying - 1) as f32 / (states - 1) as f32;
+                gradient(&[(0.0, channels(theme.alive)), (1.0, dead)], t)
+            }
+        },
+        RenderMode::Age => {
+            if !cell.is_alive() {
+                return theme.dead;
+            }
+            let t = (cell.age as f32).ln() / AGE_SCALE.ln();
+            let [young, older, old, oldest] = theme.age.map(channels);
+            gradient(&[(0.0, young), (0.3, older), (0.7, old), (1.0, oldest)], t)
+        }
+        RenderMode::BirthsDeaths => {
+            if cell.is_alive() {
+                // Just born, or just drawn
+                if cell.age <= 1 {
+                    theme.born
+                } else {
+                    theme.survived
+                }
+            } else if cell.time_since_death <= DEATH_FADE {
+                let t = cell.time_since_death as f32 / DEATH_FADE as f32;
+                gradient(&[(0.0, channels(theme.died)), (1.0, dead)], t)
+            } else {
+                theme.dead
+            }
+        }
+        RenderMode::Activity => {
+            let t = (1.0 + cell.activity as f32).ln() / (1.0 + ACTIVITY_SCALE).ln();
+            let [low, medium, high, highest] = theme.activity.map(channels);
+            gradient(
+                &[
+                    (0.0, dead),
+                    (0.25, low),
+                    (0.5, medium),
+                    (0.75, high),
+                    (1.0, highest),
+                ],
+                t,
+            )
+        }
+    }
+}
+
+pub fn draw_trails(model: &Model, canvas: &Draw) {
+    for object in model.tracker.active_objects(model.generations) {
+        // From the centre of the object
+        let points = object
+            .trail
+            .iter()
+            .map(|(x, y)| model.layout.centre(*x, *y));
+
+        canvas
+            .polyline()
+            .weight(1.0)
+            .points(points)
+            .color(model.theme.trail);
+    }
+}
+
+/// Draw the outline of the cells selected by the keyboard cursor.
+pub fn draw_cursor(cursor: &Cursor, model: &Model, canvas: &Draw) {
+    let (min_x, min_y, max_x, max_y) = cursor.selection();
+
+    let outlines = if model.layout.geometry == Geometry::Square {
+        // The selection is a rectangle of squares, from the top-left corner
+        // of the first one to the bottom-right corner of the last one
+        let (top_left, bottom_right) = (
+            model.layout.polygon(min_x, min_y)[0],
+            model.layout.polygon(max_x, max_y)[2],
+        );
+        vec![vec![
+            top_left,
+            pt2(top_left.x, bottom_right.y),
+            bottom_right,
+            pt2(bottom_right.x, top_left.y),
+        ]]
+    } else {
+        (min_x..=max_x)
+            .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+            .map(|(x, y)| model.layout.polygon(x, y))
+            .collect()
+    };
+
+    for mut outline in outlines {
+        outline.push(outline[0]);
+        canvas
+            .polyline()
+            .weight(1.5)
+            .points(outline)
+            .color(model.theme.selection);
+    }
+}
+
+pub fn draw_hud(model: &Model, canvas: &Draw) {
+    let population = match &model.plane {
+        Some(plane) => plane.population(),
+        None => stats::population(&model.cells, model.num_cells_x, model.num_cells_y),
+    };
+
+    let mut text = format!(
+        "{:?}\nGeneration: {}\nPopulation: {}\nGens/sec: {:.1}\nRule: {}",
+        model.state, model.generations, population, model.generations_per_second, model.rule
+    );
+    if model.render_mode != RenderMode::Alive {
+        text.push_str(&format!("\nColours: {:?}", model.render_mode));
+    }
+    if let Some(report) = model.stabilisation {
+        text.push_str(&format!("\nStable: {report}"));
+    }
+
+    let margin = 8.0;
+    let w = PLOT_WIDTH;
+    let h = text.lines().count() as f32 * 15.0 + 8.0;
+    let x = -model.app_width * 0.5 + margin + w * 0.5;
+    let y = model.app_height * 0.5 - margin - h * 0.5;
+
+    canvas
+        .rect()
+        .x_y(x, y)
+        .w_h(w, h)
+        .color(translucent(model.theme.hud_background, 0.6));
 
     canvas
-        .quad()
-        .w(size)
-        .h(size)
-        .x_y(real_x, real_y)
-        .color(color);
+        .text(&text)
+        .x_y(x + 4.0, y)
+        .w_h(w, h - 8.0)
+        .font_size(12)
+        .left_justify()
+        .align_text_top()
+        .color(model.theme.hud);
 }
 
-pub fn closest_n_points(in_point: Point2, points: &Vec<Point2>, n: usize) -> Vec<Point2> {
+// What just happened, in the top-right corner. Without the HUD only the
+// warnings and errors, since nothing else would say that something went wrong
+pub fn draw_messages(model: &Model, canvas: &Draw) {
+    let text: Vec<&str> = model
+        .messages
+        .iter()
+        .filter(|(_, level, _)| model.should_draw_hud || *level <= Level::Warn)
+        .map(|(message, _, _)| message.as_str())
+        .collect();
+    if text.is_empty() {
+        return;
+    }
+    let text = text.join("\n");
+
+    let margin = 8.0;
+    let w = 320.0_f32.min(model.app_width * 0.5);
+    let h = text.lines().count() as f32 * 15.0 + 8.0;
+    let x = model.app_width * 0.5 - margin - w * 0.5;
+    let y = model.app_height * 0.5 - margin - h * 0.5;
+
+    canvas
+        .rect()
+        .x_y(x, y)
+        .w_h(w, h)
+        .color(translucent(model.theme.hud_background, 0.6));
+
+    canvas
+        .text(&text)
+        .x_y(x, y)
+        .w_h(w - 8.0, h - 8.0)
+        .font_size(12)
+        .right_justify()
+        .align_text_top()
+        .color(model.theme.hud);
+}
+
+// The key bindings, in the middle of the window
+pub fn draw_help(model: &Model, canvas: &Draw) {
+    let mut lines = vec![
+        "Draw cells with the left (alive) or right (dead) mouse button".to_string(),
+        String::new(),
+    ];
+    lines.extend(model.keymap.help(model.plane.is_some()));
+    let text = lines.join("\n");
+
+    let w = 420.0_f32.min(model.app_width);
+    let h = (lines.len() as f32 * 15.0 + 16.0).min(model.app_height);
+
+    canvas
+        .rect()
+        .x_y(0.0, 0.0)
+        .w_h(w, h)
+        .color(translucent(model.theme.hud_background, 0.8));
+
+    canvas
+        .text(&text)
+        .x_y(4.0, 0.0)
+        .w_h(w - 16.0, h - 8.0)
+        .font_size(12)
+        .left_justify()
+        .align_text_top()
+        .color(model.theme.hud);
+}
+
+pub fn draw_population_plot(model: &Model, canvas: &Draw) {
+    let populations = model.stats.recent_populations(PLOT_HISTORY);
+    if populations.len() < 2 {
+        return;
+    }
+
+    let margin = 8.0;
+    let left = -model.app_width * 0.5 + margin;
+    let bottom = -model.app_height * 0.5 + margin;
+
+    canvas
+        .rect()
+        .x_y(left + PLOT_WIDTH * 0.5, bottom + PLOT_HEIGHT * 0.5)
+        .w_h(PLOT_WIDTH, PLOT_HEIGHT)
+        .color(translucent(model.theme.hud_background, 0.6));
+
+    // Scale to the highest population currently on screen,
+    // so the shape of the curve stays readable
+    let max_population = populations.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let step = PLOT_WIDTH / (PLOT_HISTORY - 1) as f32;
+
+    let points = populations.iter().enumerate().map(|(i, p)| {
+        let x = left + i as f32 * step;
+        let y = bottom + (*p as f32 / max_population) * PLOT_HEIGHT;
+        pt2(x, y)
+    });
+
+    canvas
+        .polyline()
+        .weight(1.0)
+        .points(points)
+        .color(model.theme.plot);
+}
+
+pub fn closest_n_points(in_point: Point2, points: &[Point2], n: usize) -> Vec<Point2> {
     // Find the closest distance between the given point and
     // all of the points in the grid
     let mut distances = Vec::new();
@@ -218,112 +667,367 @@ pub fn closest_n_points(in_point: Point2, points: &Vec<Point2>, n: usize) -> Vec
         distances.push((dist, pt));
     }
 
-    // Sort by distance
-    distances.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    // Sort by distance (total_cmp puts any NaN last instead of panicking)
+    distances.sort_by(|a, b| a.0.total_cmp(&b.0));
 
-    while distances.len() > n {
-        distances.pop();
-    }
+    // Fewer than n if the grid is that small
+    distances.truncate(n);
 
     let mut closest_points = Vec::new();
     for (_dist, point) in distances {
         closest_points.push(pt2(point.x, point.y));
     }
 
-    assert_eq!(closest_points.len(), n);
-
     closest_points
 }
 
 pub fn snap_to_grid(in_point: Point2, model: &Model) -> Point2 {
     // Given a input point, find the closest point on the grid (by ceiling)
     let closest_points = closest_n_points(in_point, &model.grid_points, 4);
+    if closest_points.is_empty() {
+        return in_point;
+    }
 
-    let smallest_x: f32 = closest_points
-        .iter()
-        .map(|e| e.x)
-        .reduce(|accum, item| if accum < item { accum } else { item })
-        .unwrap();
-
-    let smallest_y: f32 = closest_points
-        .iter()
-        .map(|e| e.y)
-        .reduce(|accum, item| if accum < item { accum } else { item })
-        .unwrap();
+    let smallest_x = closest_points.iter().map(|e| e.x).fold(f32::MAX, f32::min);
+    let smallest_y = closest_points.iter().map(|e| e.y).fold(f32::MAX, f32::min);
 
     pt2(smallest_x, smallest_y)
 }
 
-pub fn get_all_cells_as_dead() -> [CellsRow; GRID_SIZE] {
-    [CellsRow {
-        values: [Cell { is_alive: false }; GRID_SIZE],
-    }; GRID_SIZE]
+pub fn get_all_cells_as_dead(num_cells_x: usize, num_cells_y: usize) -> Vec<CellsRow> {
+    vec![
+        CellsRow {
+            values: vec![DEAD_CELL; num_cells_y],
+        };
+        num_cells_x
+    ]
 }
 
 pub fn init_cells(num_cells_x: usize, num_cells_y: usize, randomize: bool) -> Cells {
     let mut generator = rand::thread_rng();
-    let mut rows = get_all_cells_as_dead();
-
-    for x in 0..num_cells_x {
-        let mut values = [Cell { is_alive: false }; GRID_SIZE];
+    let mut rows = get_all_cells_as_dead(num_cells_x, num_cells_y);
 
-        for y in 0..num_cells_y {
-            if randomize {
-                values[y].is_alive = generator.gen_bool(0.5);
-            } else {
-                values[y].is_alive = false;
+    for row in rows.iter_mut() {
+        for value in row.values.iter_mut() {
+            if randomize && generator.gen_bool(0.5) {
+                *value = ALIVE_CELL;
             }
         }
-        let row = CellsRow { values };
-
-        rows[x] = row;
     }
 
     Cells { rows }
 }
 
-pub fn game_of_life(model: &mut Model) {
-    // Shorthands
-    let cells = model.cells;
-    let rows = cells.rows;
+/// A random soup, or for elementary rules the given first row.
+pub fn initial_cells(
+    rule: &Rule,
+    initial_row: &InitialRow,
+    num_cells_x: usize,
+    num_cells_y: usize,
+) -> Cells {
+    match rule.elementary {
+        Some(_) => elementary::initial_cells(initial_row, num_cells_x, num_cells_y),
+        None => init_cells(num_cells_x, num_cells_y, true),
+    }
+}
 
-    for i in 0..model.num_cells_x {
-        let mut row = rows[i];
+/// A cell drawn by hand in the given state: it has no history yet, unlike
+/// going through `next_cell()`, which counts it as a generation gone by.
+pub fn fresh_cell(state: u8) -> Cell {
+    match state {
+        rules::DEAD => DEAD_CELL,
+        rules::ALIVE => Cell {
+            age: 0,
+            ..ALIVE_CELL
+        },
+        dying => Cell {
+            state: dying,
+            ..DEAD_CELL
+        },
+    }
+}
 
-        for j in 0..model.num_cells_y {
-            // Find neighbours
-            let neighbours_indices = get_neighbours_indices(i, j, cells);
+/// The cell after one more generation, in which it is in the given state.
+pub fn next_cell(cell: Cell, state: u8) -> Cell {
+    let was_alive = cell.is_alive();
+    let is_alive = state == rules::ALIVE;
 
-            let mut alive_neighbours = Vec::new();
-            let mut dead_neighbours = Vec::new();
+    let activity = if is_alive != was_alive {
+        cell.activity.saturating_add(1)
+    } else {
+        cell.activity
+    };
 
-            for cell_index in neighbours_indices {
-                let cell = rows[cell_index.x].values[cell_index.y];
-                if cell.is_alive {
-                    alive_neighbours.push(cell);
-                } else {
-                    dead_neighbours.push(cell);
+    match (was_alive, is_alive) {
+        // Survived
+        (true, true) => Cell {
+            age: cell.age.saturating_add(1),
+            ..cell
+        },
+        // Born
+        (false, true) => Cell {
+            activity,
+            ..ALIVE_CELL
+        },
+        // Died (or started dying)
+        (true, false) => Cell {
+            state,
+            age: 0,
+            time_since_death: 1,
+            activity,
+        },
+        // Still dead (or dying)
+        (false, false) => Cell {
+            state,
+            time_since_death: cell.time_since_death.saturating_add(1),
+            ..cell
+        },
+    }
+}
+
+pub fn step_cells(cells: &Cells, num_cells_x: usize, num_cells_y: usize, rule: &Rule) -> Cells {
+    step_cells_in_parallel(cells, num_cells_x, num_cells_y, rule, 1)
+}
+
+/// How many threads to step the board on, unless told otherwise.
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Same as `step_cells()`, with the board split into bands of rows
+/// stepped on up to `threads` threads at once.
+pub fn step_cells_in_parallel(
+    cells: &Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    rule: &Rule,
+    threads: usize,
+) -> Cells {
+    step_bands(cells, num_cells_x, num_cells_y, rule, threads, None).0
+}
+
+// What every band needs to know to step its cells
+struct Step<'a> {
+    cells: &'a Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    rule: &'a Rule,
+    // The alive neighbours of every cell, for the rules that count them all at once
+    counts: Option<Vec<u32>>,
+    // If given, only the cells in its active tiles can change
+    active: Option<&'a ActiveRegion>,
+}
+
+// One generation, on up to `threads` threads (and only in the active tiles, if given).
+// With an active region, also returns the cells that changed state
+pub(crate) fn step_bands(
+    cells: &Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    rule: &Rule,
+    threads: usize,
+    active: Option<&ActiveRegion>,
+) -> (Cells, Vec<CellIndex>) {
+    if let Some(elementary) = &rule.elementary {
+        let next = step_elementary(cells, num_cells_x, num_cells_y, elementary);
+        return (next, Vec::new());
+    }
+
+    // Bigger (or odd shaped) neighbourhoods are counted all at once,
+    // the classic 8 cells one still goes through alive_neighbours()
+    let counts = if rule.is_classic() || rule.isotropic.is_some() || rule.table.is_some() {
+        None
+    } else {
+        Some(neighbourhood::count_alive_neighbours(
+            cells,
+            num_cells_x,
+            num_cells_y,
+            &rule.neighbourhood,
+            rule.include_centre,
+            active,
+        ))
+    };
+
+    let step = Step {
+        cells,
+        num_cells_x,
+        num_cells_y,
+        rule,
+        counts,
+        active,
+    };
+
+    let mut next = cells.clone();
+    let band_size = num_cells_x.div_ceil(threads.max(1)).max(1);
+    let mut bands = next.rows[..num_cells_x].chunks_mut(band_size).enumerate();
+
+    // The first band is done on this thread, no need to start one for it
+    let first_band = bands.next();
+    let changes = thread::scope(|scope| {
+        let others: Vec<_> = bands
+            .map(|(index, band)| {
+                let step = &step;
+                scope.spawn(move || step_band(step, index * band_size, band))
+            })
+            .collect();
+
+        let mut changes = match first_band {
+            Some((_, band)) => step_band(&step, 0, band),
+            None => Vec::new(),
+        };
+        for band in others {
+            changes.extend(band.join().unwrap());
+        }
+        changes
+    });
+
+    (next, changes)
+}
+
+// The next generation of the rows from `first_x` on, as many as there are in the band.
+// Returns the cells that changed state, if there's an active region to tell
+fn step_band(step: &Step, first_x: usize, band: &mut [CellsRow]) -> Vec<CellIndex> {
+    let (cells, num_cells_y) = (step.cells, step.num_cells_y);
+    let mut changes = Vec::new();
+
+    // Looking through a rule table is slow, but the same few neighbourhoods come up again and again
+    let mut known: HashMap<Vec<u8>, u8> = HashMap::new();
+
+    for (offset, row) in band.iter_mut().enumerate() {
+        let x = first_x + offset;
+
+        // A tile at a time, so that the ones where nothing can change are only aged
+        for first_y in (0..num_cells_y).step_by(TILE_SIZE) {
+            let values = &mut row.values[first_y..(first_y + TILE_SIZE).min(num_cells_y)];
+
+            if step
+                .active
+                .is_some_and(|active| !active.is_active(x, first_y))
+            {
+                for cell in values.iter_mut() {
+                    *cell = next_cell(*cell, cell.state);
                 }
+                continue;
             }
 
-            // Do the game of life..
+            for (y, value) in (first_y..).zip(values.iter_mut()) {
+                let cell = cells.rows[x].values[y];
+                let state = next_state(step, &mut known, x, y);
+                *value = next_cell(cell, state);
 
-            // 1. Any live cell with two or three live neighbours survives
-            // 2. Any dead cell with three live neighbours becomes a live cell
-            // 3. All other live cells die in the next generation. Similarly, all other dead cells stay dead.
-            if row.values[j].is_alive {
-                match alive_neighbours.len() {
-                    2 | 3 => row.values[j] = Cell { is_alive: true },
-                    _ => row.values[j] = Cell { is_alive: false },
-                }
-            } else {
-                match alive_neighbours.len() {
-                    3 => row.values[j] = Cell { is_alive: true },
-                    _ => row.values[j] = Cell { is_alive: false },
+                if step.active.is_some() && state != cell.state {
+                    changes.push(CellIndex { x, y });
                 }
             }
         }
+    }
+
+    changes
+}
+
+// The state of the cell in the next generation, whatever the kind of rule
+fn next_state(step: &Step, known: &mut HashMap<Vec<u8>, u8>, x: usize, y: usize) -> u8 {
+    let (cells, num_cells_x, num_cells_y, rule) =
+        (step.cells, step.num_cells_x, step.num_cells_y, step.rule);
+    let cell = cells.rows[x].values[y];
+
+    if let Some(table) = &rule.table {
+        return table_state(cells, num_cells_x, num_cells_y, table, known, x, y);
+    }
 
-        model.cells.rows[i] = CellsRow { values: row.values };
+    if rule.isotropic.is_some() {
+        let configuration = hensel::configuration(cells, num_cells_x, num_cells_y, x, y);
+        return rule.next_state_isotropic(cell.state, configuration);
     }
+
+    if let Some(counts) = &step.counts {
+        let alive = counts[x * num_cells_y + y] as usize;
+        return rule.next_state(cell.state, alive);
+    }
+
+    // Count the alive neighbours (the ones past the edges are dead)
+    let alive = alive_neighbours(cells, x, y, num_cells_x, num_cells_y);
+
+    // Do the game of life (or whatever the rule says)..
+
+    // With the default B3/S23 rule:
+    // 1. Any live cell with two or three live neighbours survives
+    // 2. Any dead cell with three live neighbours becomes a live cell
+    // 3. All other live cells die in the next generation. Similarly, all other dead cells stay dead.
+    rule.next_state(cell.state, alive)
+}
+
+// The next state of a cell with a rule from a .rule file, remembering
+// what the table said for each neighbourhood seen so far
+fn table_state(
+    cells: &Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    table: &RuleTable,
+    known: &mut HashMap<Vec<u8>, u8>,
+    x: usize,
+    y: usize,
+) -> u8 {
+    let mut states = Vec::with_capacity(9);
+    states.push(cells.rows[x].values[y].state);
+
+    for (dx, dy) in table.neighbourhood.offsets() {
+        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+        let on_board =
+            nx >= 0 && ny >= 0 && (nx as usize) < num_cells_x && (ny as usize) < num_cells_y;
+        states.push(if on_board {
+            cells.rows[nx as usize].values[ny as usize].state
+        } else {
+            rules::DEAD
+        });
+    }
+
+    match known.get(&states) {
+        Some(state) => *state,
+        None => {
+            let state = table.next_state(states[0], &states[1..]);
+            known.insert(states, state);
+            state
+        }
+    }
+}
+
+// One generation of an elementary rule. The board is a space-time diagram:
+// the current generation is the bottom row, and the ones before it scroll up
+fn step_elementary(
+    cells: &Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    elementary: &Elementary,
+) -> Cells {
+    let mut next = cells.clone();
+    let bottom = match num_cells_y.checked_sub(1) {
+        Some(bottom) => bottom,
+        None => return next,
+    };
+
+    let row: Vec<bool> = (0..num_cells_x)
+        .map(|x| cells.rows[x].values[bottom].is_alive())
+        .collect();
+    let next_row = elementary.next_row(&row);
+
+    for (x, alive) in next_row.into_iter().enumerate() {
+        let values = &mut next.rows[x].values;
+        values.copy_within(1..=bottom, 0);
+
+        let state = if alive { rules::ALIVE } else { rules::DEAD };
+        values[bottom] = next_cell(cells.rows[x].values[bottom], state);
+    }
+
+    next
+}
+
+pub fn game_of_life(model: &mut Model) {
+    model.cells = model.active.step(
+        &model.cells,
+        model.num_cells_x,
+        model.num_cells_y,
+        &model.rule,
+        model.threads,
+    );
 }
@@ -0,0 +1,78 @@
+use crate::core::{self, Cells};
+use crate::rules;
+
+// Editing the board without a mouse: a cursor that moves one cell at a time,
+// and with shift held down stretches a rectangle from where it started
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Cursor {
+    pub x: usize,
+    pub y: usize,
+    // The other corner of the selected rectangle, if any
+    pub anchor: Option<(usize, usize)>,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+
+impl Cursor {
+    pub fn new(x: usize, y: usize) -> Self {
+        Cursor { x, y, anchor: None }
+    }
+
+    /// Move by one cell (or more), staying on the board. With `select`, the
+    /// rectangle grows from where the cursor was, otherwise it's dropped.
+    pub fn move_by(
+        &mut self,
+        dx: i32,
+        dy: i32,
+        num_cells_x: usize,
+        num_cells_y: usize,
+        select: bool,
+    ) {
+        if select {
+            self.anchor.get_or_insert((self.x, self.y));
+        } else {
+            self.anchor = None;
+        }
+
+        let clamp = |value: usize, delta: i32, size: usize| {
+            (value as i64 + delta as i64).clamp(0, size as i64 - 1) as usize
+        };
+        self.x = clamp(self.x, dx, num_cells_x);
+        self.y = clamp(self.y, dy, num_cells_y);
+    }
+
+    /// The selected cells as (min_x, min_y, max_x, max_y), just the one
+    /// under the cursor if nothing is selected.
+    pub fn selection(&self) -> (usize, usize, usize, usize) {
+        let (x, y) = self.anchor.unwrap_or((self.x, self.y));
+        (x.min(self.x), y.min(self.y), x.max(self.x), y.max(self.y))
+    }
+
+    /// Set every selected cell to `state`, or clear them if they all already are.
+    pub fn toggle(&self, cells: &mut Cells, state: u8) {
+        let (min_x, min_y, max_x, max_y) = self.selection();
+
+        let all_set = (min_x..=max_x)
+            .all(|x| (min_y..=max_y).all(|y| cells.rows[x].values[y].state == state));
+        let state = if all_set { rules::DEAD } else { state };
+
+        self.fill(cells, state);
+    }
+
+    /// Set every selected cell to `state`.
+    pub fn fill(&self, cells: &mut Cells, state: u8) {
+        let (min_x, min_y, max_x, max_y) = self.selection();
+
+        for row in &mut cells.rows[min_x..=max_x] {
+            for cell in &mut row.values[min_y..=max_y] {
+                if cell.state != state {
+                    *cell = core::fresh_cell(state);
+                }
+            }
+        }
+    }
+}
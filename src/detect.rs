@@ -0,0 +1,244 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use crate::core::Cells;
+use crate::error::Error;
+use crate::rules;
+
+// How far back we remember generations, i.e. the longest period we can detect
+pub const DEFAULT_MAX_PERIOD: usize = 64;
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stabilisation {
+    Extinct,
+    StillLife,
+    Oscillator { period: i64 },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StabilisationReport {
+    pub kind: Stabilisation,
+    // The first generation of the final (repeating) state
+    pub generation: i64,
+}
+
+/// What the app should do once the board has stabilised.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StableAction {
+    Continue,
+    Pause,
+    Reset,
+}
+
+// The cells of a board that aren't dead, with their state
+type Snapshot = Vec<(usize, usize, u8)>;
+
+#[derive(Debug, Clone)]
+pub struct CycleDetector {
+    pub max_period: usize,
+    // Hash -> the generation it was first seen in
+    seen: HashMap<u64, i64>,
+    order: VecDeque<(u64, i64)>,
+    // The boards themselves, to check that the same hash is the same board
+    snapshots: HashMap<i64, Snapshot>,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+pub fn hash_cells(cells: &Cells, num_cells_x: usize, num_cells_y: usize) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    for (x, row) in cells.rows.iter().enumerate().take(num_cells_x) {
+        for (y, cell) in row.values.iter().enumerate().take(num_cells_y) {
+            // Dying cells matter too, for rules with more than 2 states
+            if cell.state != rules::DEAD {
+                (x, y, cell.state).hash(&mut hasher);
+            }
+        }
+    }
+
+    hasher.finish()
+}
+
+fn snapshot(cells: &Cells, num_cells_x: usize, num_cells_y: usize) -> Snapshot {
+    let mut snapshot = Vec::new();
+
+    for (x, row) in cells.rows.iter().enumerate().take(num_cells_x) {
+        for (y, cell) in row.values.iter().enumerate().take(num_cells_y) {
+            if cell.state != rules::DEAD {
+                snapshot.push((x, y, cell.state));
+            }
+        }
+    }
+
+    snapshot
+}
+
+impl CycleDetector {
+    pub fn new(max_period: usize) -> Self {
+        CycleDetector {
+            max_period,
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            snapshots: HashMap::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+        self.snapshots.clear();
+    }
+
+    /// Feed the board of the given generation to the detector.
+    /// Returns a report the first time a generation repeats (or dies out).
+    pub fn observe(
+        &mut self,
+        generation: i64,
+        cells: &Cells,
+        num_cells_x: usize,
+        num_cells_y: usize,
+    ) -> Option<StabilisationReport> {
+        let snapshot = snapshot(cells, num_cells_x, num_cells_y);
+        let empty = snapshot.is_empty();
+        let hash = if empty {
+            0
+        } else {
+            hash_cells(cells, num_cells_x, num_cells_y)
+        };
+
+        self.observe_board(generation, hash, empty, Some(snapshot))
+    }
+
+    /// Same as `observe()`, for boards hashed some other way (eg. the infinite plane).
+    /// There's no board to compare, so two boards with the same hash are taken to be the same.
+    pub fn observe_hash(
+        &mut self,
+        generation: i64,
+        hash: u64,
+        is_empty: bool,
+    ) -> Option<StabilisationReport> {
+        self.observe_board(generation, hash, is_empty, None)
+    }
+
+    fn observe_board(
+        &mut self,
+        generation: i64,
+        hash: u64,
+        is_empty: bool,
+        snapshot: Option<Snapshot>,
+    ) -> Option<StabilisationReport> {
+        if is_empty {
+            // If the previous generation was already empty we would have
+            // reported it then, so this is the first empty generation
+            return Some(StabilisationReport {
+                kind: Stabilisation::Extinct,
+                generation,
+            });
+        }
+
+        let first_seen = self.seen.get(&hash).copied().filter(|first_seen| {
+            // Different boards with the same hash are a new generation like any other
+            match (&snapshot, self.snapshots.get(first_seen)) {
+                (Some(now), Some(then)) => now == then,
+                _ => true,
+            }
+        });
+
+        if let Some(first_seen) = first_seen {
+            let period = generation - first_seen;
+            let kind = if period == 1 {
+                Stabilisation::StillLife
+            } else {
+                Stabilisation::Oscillator { period }
+            };
+
+            return Some(StabilisationReport {
+                kind,
+                generation: first_seen,
+            });
+        }
+
+        self.seen.insert(hash, generation);
+        self.order.push_back((hash, generation));
+        if let Some(snapshot) = snapshot {
+            self.snapshots.insert(generation, snapshot);
+        }
+
+        // Forget the oldest generations, so memory doesn't grow forever
+        while self.order.len() > self.max_period {
+            if let Some((oldest, oldest_generation)) = self.order.pop_front() {
+                // Unless a newer board took its hash
+                if self.seen.get(&oldest) == Some(&oldest_generation) {
+                    self.seen.remove(&oldest);
+                }
+                self.snapshots.remove(&oldest_generation);
+            }
+        }
+
+        None
+    }
+}
+
+impl Default for CycleDetector {
+    fn default() -> Self {
+        CycleDetector::new(DEFAULT_MAX_PERIOD)
+    }
+}
+
+impl fmt::Display for StabilisationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            Stabilisation::Extinct => write!(f, "extinct at generation {}", self.generation),
+            Stabilisation::StillLife => {
+                write!(f, "still life from generation {}", self.generation)
+            }
+            Stabilisation::Oscillator { period } => write!(
+                f,
+                "period {period} oscillator from generation {}",
+                self.generation
+            ),
+        }
+    }
+}
+
+impl StableAction {
+    pub fn next(self) -> Self {
+        match self {
+            StableAction::Continue => StableAction::Pause,
+            StableAction::Pause => StableAction::Reset,
+            StableAction::Reset => StableAction::Continue,
+        }
+    }
+}
+
+impl fmt::Display for StableAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            StableAction::Continue => "continue",
+            StableAction::Pause => "pause",
+            StableAction::Reset => "reset",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for StableAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "continue" => Ok(StableAction::Continue),
+            "pause" => Ok(StableAction::Pause),
+            "reset" => Ok(StableAction::Reset),
+            _ => Err(Error::Invalid(format!(
+                "Unknown action '{s}', expected one of: continue, pause, reset"
+            ))),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::core::{self, Cells};
+use crate::error::Error;
+
+// Elementary (one-dimensional) automata, numbered like Wolfram does: bit n of
+// the number is the next state of a cell whose left neighbour, itself and
+// right neighbour spell n in binary. See
+// https://mathworld.wolfram.com/ElementaryCellularAutomaton.html
+
+// Data structures
+// ----------------------------------------------------------------------------
+
+/// What the cells past the ends of the row are.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Boundary {
+    #[default]
+    Dead,
+    Alive,
+    // The row wraps around, the last cell is next to the first one
+    Wrap,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Elementary {
+    pub number: u8,
+    pub boundary: Boundary,
+}
+
+/// The row the automaton starts from.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum InitialRow {
+    // A single alive cell in the middle
+    #[default]
+    Seed,
+    Random,
+    // Given as text, eg. "..o.oo" or "001011", centred on the row
+    Cells(Vec<bool>),
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+impl Elementary {
+    pub fn new(number: u8) -> Self {
+        Elementary {
+            number,
+            boundary: Boundary::default(),
+        }
+    }
+
+    /// The next generation of a row of cells.
+    pub fn next_row(&self, row: &[bool]) -> Vec<bool> {
+        let width = row.len() as i64;
+        let cell = |x: i64| match self.boundary {
+            _ if (0..width).contains(&x) => row[x as usize],
+            Boundary::Dead => false,
+            Boundary::Alive => true,
+            Boundary::Wrap => row[x.rem_euclid(width) as usize],
+        };
+
+        (0..width)
+            .map(|x| {
+                let index = (cell(x - 1) as u8) << 2 | (cell(x) as u8) << 1 | cell(x + 1) as u8;
+                self.number & (1 << index) != 0
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Boundary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Boundary::Dead => "dead",
+            Boundary::Alive => "alive",
+            Boundary::Wrap => "wrap",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Boundary {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dead" => Ok(Boundary::Dead),
+            "alive" => Ok(Boundary::Alive),
+            "wrap" => Ok(Boundary::Wrap),
+            _ => Err(Error::Invalid(format!(
+                "Unknown boundary '{s}', expected one of: dead, alive, wrap"
+            ))),
+        }
+    }
+}
+
+impl FromStr for InitialRow {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            Error::Invalid(format!(
+                "Invalid row '{s}', expected seed, random or cells like '..o.oo'"
+            ))
+        };
+
+        match s {
+            "seed" => Ok(InitialRow::Seed),
+            "random" => Ok(InitialRow::Random),
+            "" => Err(invalid()),
+            _ => s
+                .chars()
+                .map(|c| match c {
+                    '.' | '0' | 'b' => Ok(false),
+                    'o' | '1' | '*' => Ok(true),
+                    _ => Err(invalid()),
+                })
+                .collect::<Result<Vec<bool>, Error>>()
+                .map(InitialRow::Cells),
+        }
+    }
+}
+
+/// The first generation, on the bottom row of an otherwise empty board.
+pub fn initial_cells(row: &InitialRow, num_cells_x: usize, num_cells_y: usize) -> Cells {
+    let mut cells = core::init_cells(num_cells_x, num_cells_y, false);
+    if num_cells_y == 0 {
+        return cells;
+    }
+
+    let alive: Vec<bool> = match row {
+        InitialRow::Seed => (0..num_cells_x).map(|x| x == num_cells_x / 2).collect(),
+        InitialRow::Random => {
+            let mut generator = rand::thread_rng();
+            (0..num_cells_x).map(|_| generator.gen_bool(0.5)).collect()
+        }
+        InitialRow::Cells(values) => {
+            // Centred, and clipped if it's wider than the board
+            let offset = num_cells_x as i64 / 2 - values.len() as i64 / 2;
+            (0..num_cells_x as i64)
+                .map(|x| {
+                    let i = x - offset;
+                    i >= 0 && (i as usize) < values.len() && values[i as usize]
+                })
+                .collect()
+        }
+    };
+
+    for (x, alive) in alive.into_iter().enumerate() {
+        if alive {
+            cells.rows[x].values[num_cells_y - 1] = core::ALIVE_CELL;
+        }
+    }
+
+    cells
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// What can go wrong when loading patterns, rules and configs, parsing the
+// command line or drawing. The library returns these instead of panicking,
+// and the app shows them as a message and carries on.
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug)]
+pub enum Error {
+    // A file that couldn't be read or written
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    // A pattern, rule table, theme, keymap.. that doesn't parse. The line
+    // and column count from 1, and are left out when there's no telling
+    Parse {
+        path: Option<PathBuf>,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    // A value that doesn't make sense: a rulestring, an argument, two
+    // actions on the same key..
+    Invalid(String),
+    // The window couldn't be opened, or a frame drawn
+    Render(String),
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+
+impl Error {
+    pub fn io<P: AsRef<Path>>(path: P, source: io::Error) -> Self {
+        Error::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        }
+    }
+
+    /// A parse error on a line (and column) of the text, not knowing which file yet.
+    pub fn parse(line: usize, column: Option<usize>, message: impl Into<String>) -> Self {
+        Error::Parse {
+            path: None,
+            line: Some(line),
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// The same error, coming from the given file.
+    pub fn in_file<P: AsRef<Path>>(self, file: P) -> Self {
+        let file = Some(file.as_ref().to_path_buf());
+        match self {
+            Error::Parse {
+                path: None,
+                line,
+                column,
+                message,
+            } => Error::Parse {
+                path: file,
+                line,
+                column,
+                message,
+            },
+            Error::Invalid(message) => Error::Parse {
+                path: file,
+                line: None,
+                column: None,
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Error::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => {
+                // Like compilers do, eg. glider.rle:3:7: unexpected 'z'
+                if let Some(path) = path {
+                    write!(f, "{}:", path.display())?;
+                }
+                if let Some(line) = line {
+                    write!(f, "{line}:")?;
+                }
+                if let Some(column) = column {
+                    write!(f, "{column}:")?;
+                }
+                if path.is_some() || line.is_some() {
+                    write!(f, " ")?;
+                }
+                write!(f, "{message}")
+            }
+            Error::Invalid(message) => write!(f, "{message}"),
+            Error::Render(message) => write!(f, "Failed to draw: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Invalid(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::Invalid(message.to_string())
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        // The message ends with where it went wrong, which goes in front instead
+        let text = e.to_string();
+        let message = match text.rsplit_once(" at line ") {
+            Some((message, _)) => message.to_string(),
+            None => text,
+        };
+        match e.line_col() {
+            Some((line, column)) => Error::parse(line + 1, Some(column + 1), message),
+            None => Error::Invalid(message),
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use std::fmt;
+use std::str::FromStr;
+
+use nannou::prelude::*;
+
+use crate::core::{Line, GRID_LINE_WEIGHT};
+use crate::error::Error;
+use crate::neighbourhood::{self, Neighbourhood, Shape};
+
+const SQRT_3: f32 = 1.732_050_8;
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Geometry {
+    Square,
+    // Pointy-top hexagons, each row shifted by half a cell to the left of the
+    // one above (like LifeViewer shows Golly's hexagonal rules)
+    Hexagonal,
+    // Triangles pointing up when x + y is even, down otherwise
+    Triangular,
+}
+
+/// Where the cells of the board are on screen (in nannou's 0,0-is-center coordinates).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Layout {
+    pub geometry: Geometry,
+    pub num_cells_x: usize,
+    pub num_cells_y: usize,
+    // Width of a square, hexagon or triangle (base)
+    pub cell_width: f32,
+    // Height of a square or triangle, distance between rows of hexagons
+    pub cell_height: f32,
+    // Top-left corner of the board
+    pub left: f32,
+    pub top: f32,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+impl Geometry {
+    /// The geometry the neighbourhood of a rule was made for.
+    pub fn of(neighbourhood: &Neighbourhood) -> Self {
+        match neighbourhood.shape {
+            Shape::Hexagonal => Geometry::Hexagonal,
+            Shape::Triangular => Geometry::Triangular,
+            _ => Geometry::Square,
+        }
+    }
+
+    /// The cells sharing an edge or a corner.
+    pub fn neighbourhood(self) -> Neighbourhood {
+        match self {
+            Geometry::Square => Neighbourhood::moore(1),
+            Geometry::Hexagonal => Neighbourhood::hexagonal(1),
+            Geometry::Triangular => Neighbourhood::triangular(),
+        }
+    }
+}
+
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Geometry::Square => "square",
+            Geometry::Hexagonal => "hex",
+            Geometry::Triangular => "triangle",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Geometry {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "square" => Ok(Geometry::Square),
+            "hex" | "hexagonal" => Ok(Geometry::Hexagonal),
+            "triangle" | "triangular" => Ok(Geometry::Triangular),
+            _ => Err(Error::Invalid(format!(
+                "Unknown geometry '{s}', expected one of: square, hex, triangle"
+            ))),
+        }
+    }
+}
+
+// Whether the point is inside the convex polygon (given in either order)
+fn contains(polygon: &[Point2], point: Point2) -> bool {
+    let mut sign = 0.0;
+
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+        if cross * sign < 0.0 {
+            return false;
+        }
+        if cross != 0.0 {
+            sign = cross;
+        }
+    }
+
+    true
+}
+
+impl Layout {
+    /// Fit the board in the window: squares keep the given size (and the
+    /// top-left corner of the window), the other cells are scaled to fit.
+    pub fn new(
+        geometry: Geometry,
+        app_width: f32,
+        app_height: f32,
+        num_cells_x: usize,
+        num_cells_y: usize,
+        cell_size: usize,
+    ) -> Self {
+        let (nx, ny) = (num_cells_x as f32, num_cells_y as f32);
+
+        let (cell_width, cell_height, board_width, board_height) = match geometry {
+            Geometry::Square => {
+                let size = cell_size as f32;
+                (size, size, app_width, app_height)
+            }
+            Geometry::Hexagonal => {
+                let width = (app_width / (nx + (ny - 1.0) * 0.5))
+                    .min(app_height / ((ny - 1.0) * SQRT_3 * 0.5 + 2.0 / SQRT_3));
+                let row_height = width * SQRT_3 * 0.5;
+                (
+                    width,
+                    row_height,
+                    (nx + (ny - 1.0) * 0.5) * width,
+                    (ny - 1.0) * row_height + 2.0 * width / SQRT_3,
+                )
+            }
+            Geometry::Triangular => {
+                let width = (2.0 * app_width / (nx + 1.0)).min(app_height / (ny * SQRT_3 * 0.5));
+                let height = width * SQRT_3 * 0.5;
+                (width, height, (nx + 1.0) * width * 0.5, ny * height)
+            }
+        };
+
+        Layout {
+            geometry,
+            num_cells_x,
+            num_cells_y,
+            cell_width,
+            cell_height,
+            left: -board_width * 0.5,
+            top: board_height * 0.5,
+        }
+    }
+
+    /// The same board in a window of another size. Squares keep their size, so
+    /// the window shows more or less of the board, the other cells are scaled.
+    pub fn resized(&self, app_width: f32, app_height: f32, cell_size: usize) -> Self {
+        Layout::new(
+            self.geometry,
+            app_width,
+            app_height,
+            self.num_cells_x,
+            self.num_cells_y,
+            cell_size,
+        )
+    }
+
+    // Radius of the hexagons (centre to corner)
+    fn hex_radius(&self) -> f32 {
+        self.cell_width / SQRT_3
+    }
+
+    /// The centre of a cell. Takes floats so that it works for
+    /// positions in between cells too (eg. the centre of an object).
+    pub fn centre(&self, x: f32, y: f32) -> Point2 {
+        let (w, h) = (self.cell_width, self.cell_height);
+
+        match self.geometry {
+            Geometry::Square => pt2(self.left + (x + 0.5) * w, self.top - (y + 0.5) * h),
+            Geometry::Hexagonal => {
+                let shift = (self.num_cells_y as f32 - 1.0) * 0.5;
+                pt2(
+                    self.left + (x - y * 0.5 + shift + 0.5) * w,
+                    self.top - self.hex_radius() - y * h,
+                )
+            }
+            Geometry::Triangular => pt2(self.left + (x + 1.0) * w * 0.5, self.top - (y + 0.5) * h),
+        }
+    }
+
+    /// The corners of a cell, counter-clockwise.
+    pub fn polygon(&self, x: usize, y: usize) -> Vec<Point2> {
+        let (w, h) = (self.cell_width, self.cell_height);
+
+        match self.geometry {
+            Geometry::Square => {
+                let left = self.left + x as f32 * w;
+                let top = self.top - y as f32 * h;
+                vec![
+                    pt2(left, top),
+                    pt2(left, top - h),
+                    pt2(left + w, top - h),
+                    pt2(left + w, top),
+                ]
+            }
+            Geometry::Hexagonal => {
+                let centre = self.centre(x as f32, y as f32);
+                let r = self.hex_radius();
+                (0..6)
+                    .map(|i| {
+                        let angle = (30.0 + 60.0 * i as f32).to_radians();
+                        pt2(centre.x + r * angle.cos(), centre.y + r * angle.sin())
+                    })
+                    .collect()
+            }
+            Geometry::Triangular => {
+                let left = self.left + x as f32 * w * 0.5;
+                let top = self.top - y as f32 * h;
+                if neighbourhood::points_up(x, y) {
+                    vec![
+                        pt2(left + w * 0.5, top),
+                        pt2(left, top - h),
+                        pt2(left + w, top - h),
+                    ]
+                } else {
+                    vec![
+                        pt2(left, top),
+                        pt2(left + w * 0.5, top - h),
+                        pt2(left + w, top),
+                    ]
+                }
+            }
+        }
+    }
+
+    fn is_on_board(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.num_cells_x && (y as usize) < self.num_cells_y
+    }
+
+    /// The cell under the given point, if any.
+    pub fn cell_at(&self, point: Point2) -> Option<(usize, usize)> {
+        let (w, h) = (self.cell_width, self.cell_height);
+
+        let (x, y) = match self.geometry {
+            Geometry::Square => (
+                ((point.x - self.left) / w).floor() as i64,
+                ((self.top - point.y) / h).floor() as i64,
+            ),
+            Geometry::Hexagonal => {
+                // Back to (fractional) axial coordinates, then round to the
+                // closest hexagon, see https://www.redblobgames.com/grids/hexagons/
+                let shift = (self.num_cells_y as f32 - 1.0) * 0.5;
+                let fy = (self.top - self.hex_radius() - point.y) / h;
+                let fx = (point.x - self.left) / w - 0.5 - shift + fy * 0.5;
+
+                let (q, r) = (fx - fy, fy);
+                let s = -q - r;
+                let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+                let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+                if dq > dr && dq > ds {
+                    rq = -rr - rs;
+                } else if dr > ds {
+                    rr = -rq - rs;
+                }
+
+                ((rq + rr) as i64, rr as i64)
+            }
+            Geometry::Triangular => {
+                let y = ((self.top - point.y) / h).floor() as i64;
+                let u = ((point.x - self.left) / (w * 0.5)).floor() as i64;
+                let x = (u - 1..=u)
+                    .filter(|x| self.is_on_board(*x, y))
+                    .find(|x| contains(&self.polygon(*x as usize, y as usize), point));
+                match x {
+                    Some(x) => (x, y),
+                    None => return None,
+                }
+            }
+        };
+
+        if self.is_on_board(x, y) {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    /// The edges between the cells (and around the board), each drawn once.
+    pub fn grid_lines(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        for x in 0..self.num_cells_x {
+            for y in 0..self.num_cells_y {
+                let corners = self.polygon(x, y);
+                let (xi, yi) = (x as i64, y as i64);
+
+                // Each edge (from one corner to the next) with the cell on the other side:
+                // the edge is drawn by the first of the two cells, or if there's no other cell
+                let edges: Vec<(usize, (i64, i64))> = match self.geometry {
+                    Geometry::Square => vec![
+                        (0, (xi - 1, yi)),
+                        (1, (xi, yi + 1)),
+                        (2, (xi + 1, yi)),
+                        (3, (xi, yi - 1)),
+                    ],
+                    Geometry::Hexagonal => vec![
+                        (0, (xi, yi - 1)),
+                        (1, (xi - 1, yi - 1)),
+                        (2, (xi - 1, yi)),
+                        (3, (xi, yi + 1)),
+                        (4, (xi + 1, yi + 1)),
+                        (5, (xi + 1, yi)),
+                    ],
+                    Geometry::Triangular if neighbourhood::points_up(x, y) => {
+                        vec![(0, (xi - 1, yi)), (1, (xi, yi + 1)), (2, (xi + 1, yi))]
+                    }
+                    Geometry::Triangular => {
+                        vec![(0, (xi - 1, yi)), (1, (xi + 1, yi)), (2, (xi, yi - 1))]
+                    }
+                };
+
+                for (corner, (ox, oy)) in edges {
+                    let other_first = (oy, ox) < (yi, xi);
+                    if other_first && self.is_on_board(ox, oy) {
+                        continue;
+                    }
+
+                    let start = corners[corner];
+                    let end = corners[(corner + 1) % corners.len()];
+                    lines.push(Line {
+                        start_x: start.x,
+                        start_y: start.y,
+                        end_x: end.x,
+                        end_y: end.y,
+                        weight: GRID_LINE_WEIGHT,
+                    });
+                }
+            }
+        }
+
+        lines
+    }
+}
@@ -0,0 +1,233 @@
+use crate::active::ActiveRegion;
+use crate::census::{self, Census};
+use crate::cli::Options;
+use crate::core::{self, Cells};
+use crate::detect::{CycleDetector, Stabilisation, StabilisationReport};
+use crate::error::Error;
+use crate::logging;
+use crate::plane::{self, Plane};
+use crate::rle::{self, Pattern};
+use crate::rules::Rule;
+use crate::tracking::Tracker;
+
+// Data structures
+// ----------------------------------------------------------------------------
+pub struct HeadlessRun {
+    pub cells: Cells,
+    pub generations: i64,
+    pub stabilisation: Option<StabilisationReport>,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+
+/// Step the given board until it stabilises or `max_generations` is reached.
+/// If a tracker is given, it sees every generation along the way.
+pub fn run_until_stable(
+    mut cells: Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    rule: &Rule,
+    threads: usize,
+    max_generations: i64,
+    mut tracker: Option<&mut Tracker>,
+) -> HeadlessRun {
+    let mut detector = CycleDetector::default();
+    let mut active = ActiveRegion::new();
+    let mut generations = 0;
+
+    let mut stabilisation = detector.observe(generations, &cells, num_cells_x, num_cells_y);
+
+    while stabilisation.is_none() && generations < max_generations {
+        cells = active.step(&cells, num_cells_x, num_cells_y, rule, threads);
+        generations += 1;
+        log::trace!(target: logging::SIMULATION, "Generation: {generations}");
+
+        if let Some(tracker) = tracker.as_deref_mut() {
+            tracker.observe(generations, &cells, num_cells_x, num_cells_y);
+        }
+
+        stabilisation = detector.observe(generations, &cells, num_cells_x, num_cells_y);
+    }
+
+    HeadlessRun {
+        cells,
+        generations,
+        stabilisation,
+    }
+}
+
+/// Step the plane until it stabilises or `max_generations` is reached.
+pub fn run_plane_until_stable(
+    mut plane: Plane,
+    rule: &Rule,
+    max_generations: i64,
+) -> (Plane, i64, Option<StabilisationReport>) {
+    let mut detector = CycleDetector::default();
+    let mut generations = 0;
+
+    let mut stabilisation =
+        detector.observe_hash(generations, plane.hash(), plane.cells.is_empty());
+
+    while stabilisation.is_none() && generations < max_generations {
+        plane = plane.step(rule);
+        generations += 1;
+        log::trace!(target: logging::SIMULATION, "Generation: {generations}");
+        stabilisation = detector.observe_hash(generations, plane.hash(), plane.cells.is_empty());
+    }
+
+    (plane, generations, stabilisation)
+}
+
+// Same as run(), on the infinite plane
+fn run_plane(options: &Options, pattern: Option<&Pattern>, rule: &Rule) -> Result<Census, Error> {
+    if options.census.is_some() || options.track {
+        return Err(Error::Invalid(String::from(
+            "--census and --track only work on the fixed size board, not with --infinite",
+        )));
+    }
+    plane::check_rule(rule)?;
+
+    let soups = if pattern.is_some() { 1 } else { options.soups };
+    let (width, height) = options.size;
+
+    for soup in 1..=soups {
+        let start = match pattern {
+            Some(pattern) => {
+                log::info!(
+                    target: logging::SIMULATION,
+                    "Running {} with {rule} on the infinite plane..",
+                    options.pattern.as_deref().unwrap_or("")
+                );
+                Plane::from_pattern(pattern)
+            }
+            None => {
+                log::info!(
+                    target: logging::SIMULATION,
+                    "Running random {width}x{height} soup {soup}/{soups} with {rule} on the infinite plane.."
+                );
+                Plane::random(width, height)
+            }
+        };
+
+        let (plane, generations, stabilisation) =
+            run_plane_until_stable(start, rule, options.max_generations);
+
+        match stabilisation {
+            Some(report) => println!("Stabilised: {report} (ran {generations} generations)"),
+            None => println!(
+                "Did not stabilise within {} generations",
+                options.max_generations
+            ),
+        }
+        match plane.bounds() {
+            Some((min_x, min_y, max_x, max_y)) => println!(
+                "Population {}, from {min_x},{min_y} to {max_x},{max_y}",
+                plane.population()
+            ),
+            None => println!("Population 0"),
+        }
+    }
+
+    Ok(Census::new())
+}
+
+pub fn run(options: &Options) -> Result<Census, Error> {
+    let (num_cells_x, num_cells_y) = options.size;
+
+    // Either the given pattern (once), or as many random soups as requested
+    let pattern = match &options.pattern {
+        Some(path) => Some(rle::load_rle(path)?),
+        None => None,
+    };
+    let rule = options.rule_for(pattern.as_ref());
+    let soups = if pattern.is_some() { 1 } else { options.soups };
+
+    if options.infinite {
+        return run_plane(options, pattern.as_ref(), &rule);
+    }
+
+    let mut total = Census::new();
+
+    for soup in 1..=soups {
+        let cells = match &pattern {
+            Some(pattern) => {
+                log::info!(
+                    target: logging::SIMULATION,
+                    "Running {} with {rule}..",
+                    options.pattern.as_deref().unwrap_or("")
+                );
+                let (cells, clipped) = rle::centred_pattern(pattern, num_cells_x, num_cells_y);
+                if clipped > 0 {
+                    log::warn!(
+                        target: logging::IO,
+                        "{clipped} cells of the pattern didn't fit on the board"
+                    );
+                }
+                cells
+            }
+            None if rule.elementary.is_some() => {
+                log::info!(
+                    target: logging::SIMULATION,
+                    "Running {rule} on a row of {num_cells_x} cells ({soup}/{soups}).."
+                );
+                core::initial_cells(&rule, &options.initial_row, num_cells_x, num_cells_y)
+            }
+            None => {
+                log::info!(
+                    target: logging::SIMULATION,
+                    "Running random {num_cells_x}x{num_cells_y} soup {soup}/{soups} with {rule}.."
+                );
+                core::initial_cells(&rule, &options.initial_row, num_cells_x, num_cells_y)
+            }
+        };
+
+        let mut tracker = Tracker::new();
+        let run = run_until_stable(
+            cells,
+            num_cells_x,
+            num_cells_y,
+            &rule,
+            options.threads,
+            options.max_generations,
+            options.track.then_some(&mut tracker),
+        );
+
+        let period = match run.stabilisation {
+            Some(report) => {
+                println!("Stabilised: {report} (ran {} generations)", run.generations);
+                match report.kind {
+                    Stabilisation::Oscillator { period } => period as usize,
+                    _ => 1,
+                }
+            }
+            None => {
+                println!(
+                    "Did not stabilise within {} generations",
+                    options.max_generations
+                );
+                1
+            }
+        };
+
+        if options.track {
+            print!("{}", tracker.summary());
+        }
+
+        if options.census.is_some() {
+            total.merge(&census::take_census(
+                &run.cells,
+                num_cells_x,
+                num_cells_y,
+                period,
+                &rule,
+            ));
+        }
+    }
+
+    if let Some(format) = options.census {
+        print!("{}", total.format(format));
+    }
+
+    Ok(total)
+}
@@ -0,0 +1,237 @@
+use crate::core::Cells;
+
+// Isotropic non-totalistic rules, in Hensel notation: "B2n3/S23-q" means
+// born with 3 neighbours or 2 "n" neighbours, survives with 2 neighbours or
+// with 3 unless they are "q". The letters name the 8 cells around up to
+// rotations and reflections, see
+// https://conwaylife.com/wiki/Isotropic_non-totalistic_rule
+
+// The bit of each neighbour in a configuration, by (dx, dy)
+pub const NEIGHBOUR_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+// The letters for 1 to 4 neighbours, in the same order as Golly.
+// 5 to 7 neighbours use the same letters as 8 - n, on the dead cells
+const LETTERS: [&str; 5] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrtwyz"];
+
+// One configuration for each letter above
+const REPRESENTATIVES: [&[u8]; 5] = [
+    &[0b00000000],
+    &[0b00000001, 0b00000010],
+    &[
+        0b00000101, 0b00001010, 0b00000011, 0b00011000, 0b00010001, 0b00100100,
+    ],
+    &[
+        0b00100101, 0b00011010, 0b00001011, 0b00000111, 0b00110010, 0b00001101, 0b00001110,
+        0b00100110, 0b00011001, 0b00110001,
+    ],
+    &[
+        0b10100101, 0b01011010, 0b00001111, 0b00011101, 0b00110011, 0b00100111, 0b00111010,
+        0b00110110, 0b00011011, 0b00110101, 0b00111001, 0b00101110, 0b00111100,
+    ],
+];
+
+// Data structures
+// ----------------------------------------------------------------------------
+
+/// Whether a cell is born (or survives), for each of the 256 configurations.
+pub type Transitions = [bool; 256];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Isotropic {
+    pub birth: Transitions,
+    pub survival: Transitions,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+fn letters(count: usize) -> &'static str {
+    LETTERS[count.min(8 - count)]
+}
+
+fn representative(count: usize, letter_index: usize) -> u8 {
+    if count <= 4 {
+        REPRESENTATIVES[count][letter_index]
+    } else {
+        !REPRESENTATIVES[8 - count][letter_index]
+    }
+}
+
+// Apply one of the 8 symmetries of the square to a configuration
+fn transform(configuration: u8, symmetry: usize) -> u8 {
+    let mut transformed = 0;
+
+    for (bit, (dx, dy)) in NEIGHBOUR_OFFSETS.iter().enumerate() {
+        if configuration & (1 << bit) == 0 {
+            continue;
+        }
+
+        let (dx, dy) = match symmetry {
+            0 => (*dx, *dy),
+            1 => (-dy, *dx),
+            2 => (-dx, -dy),
+            3 => (*dy, -dx),
+            4 => (-dx, *dy),
+            5 => (*dx, -dy),
+            6 => (*dy, *dx),
+            _ => (-dy, -dx),
+        };
+        let index = NEIGHBOUR_OFFSETS
+            .iter()
+            .position(|o| *o == (dx, dy))
+            .unwrap();
+        transformed |= 1 << index;
+    }
+
+    transformed
+}
+
+/// The letter of every configuration (None for 0 and 8 neighbours).
+pub fn configuration_letters() -> [Option<char>; 256] {
+    let mut table = [None; 256];
+
+    for count in 1..8 {
+        for (index, letter) in letters(count).chars().enumerate() {
+            let configuration = representative(count, index);
+            for symmetry in 0..8 {
+                table[transform(configuration, symmetry) as usize] = Some(letter);
+            }
+        }
+    }
+
+    table
+}
+
+/// Which neighbours of the cell are alive, anything outside of the board is dead.
+pub fn configuration(
+    cells: &Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    x: usize,
+    y: usize,
+) -> u8 {
+    let mut configuration = 0;
+
+    for (bit, (dx, dy)) in NEIGHBOUR_OFFSETS.iter().enumerate() {
+        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+        if nx < 0 || ny < 0 || nx as usize >= num_cells_x || ny as usize >= num_cells_y {
+            continue;
+        }
+        if cells.rows[nx as usize].values[ny as usize].is_alive() {
+            configuration |= 1 << bit;
+        }
+    }
+
+    configuration
+}
+
+/// Whether the B or S part of a rule (without the letter) uses Hensel letters.
+pub fn is_hensel(part: &str) -> bool {
+    part.chars().any(|c| c == '-' || c.is_ascii_lowercase())
+}
+
+/// Parse the B or S part of a rule (without the letter), eg. "2n3" or "23-q".
+pub fn parse_transitions(part: &str, rulestring: &str) -> Result<Transitions, String> {
+    let table = configuration_letters();
+    let mut transitions = [false; 256];
+    let mut chars = part.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let count = match c.to_digit(10) {
+            Some(n) if n <= 8 => n as usize,
+            _ => {
+                return Err(format!(
+                    "Invalid rule '{rulestring}': '{c}' is not a number of neighbours (0-8)"
+                ))
+            }
+        };
+
+        let negated = chars.next_if_eq(&'-').is_some();
+        let mut chosen = String::new();
+        while let Some(letter) = chars.next_if(|c| c.is_ascii_lowercase()) {
+            if !letters(count).contains(letter) {
+                return Err(format!(
+                    "Invalid rule '{rulestring}': there is no '{count}{letter}'"
+                ));
+            }
+            chosen.push(letter);
+        }
+        if negated && chosen.is_empty() {
+            return Err(format!(
+                "Invalid rule '{rulestring}': expected letters after '{count}-'"
+            ));
+        }
+
+        for configuration in 0..256 {
+            if (configuration as u8).count_ones() as usize != count {
+                continue;
+            }
+            let included = match table[configuration] {
+                Some(letter) if !chosen.is_empty() => chosen.contains(letter) != negated,
+                _ => true,
+            };
+            if included {
+                transitions[configuration] = true;
+            }
+        }
+    }
+
+    Ok(transitions)
+}
+
+/// The shortest Hensel notation for the transitions, eg. "23-q" rather than "23cekaiknjry".
+pub fn format_transitions(transitions: &Transitions) -> String {
+    let table = configuration_letters();
+    let mut text = String::new();
+
+    for count in 0..=8 {
+        let all = letters(count);
+        let chosen: String = all
+            .chars()
+            .filter(|letter| {
+                (0..256).any(|c| {
+                    transitions[c]
+                        && table[c] == Some(*letter)
+                        && (c as u8).count_ones() == count as u32
+                })
+            })
+            .collect();
+
+        let any = (0..256).any(|c| transitions[c] && (c as u8).count_ones() == count as u32);
+        if !any {
+            continue;
+        }
+
+        text.push_str(&count.to_string());
+        if chosen.len() == all.len() {
+            continue;
+        }
+        if chosen.len() * 2 > all.len() {
+            text.push('-');
+            text.extend(all.chars().filter(|l| !chosen.contains(*l)));
+        } else {
+            text.push_str(&chosen);
+        }
+    }
+
+    text
+}
+
+/// For each number of neighbours, whether every configuration with that many is in.
+pub fn totalistic_counts(transitions: &Transitions) -> Vec<bool> {
+    (0..=8)
+        .map(|count| {
+            (0..256)
+                .filter(|c| (*c as u8).count_ones() == count)
+                .all(|c| transitions[c])
+        })
+        .collect()
+}
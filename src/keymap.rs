@@ -0,0 +1,632 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use nannou::event::ModifiersState;
+use nannou::prelude::Key;
+
+use crate::error::Error;
+
+// Which key does what. The defaults can be changed with a TOML file that
+// maps action names to keys (or lists of keys), eg:
+//
+//     start = "space"
+//     save = "ctrl+s"
+//     zoom-in = ["=", "+", "numpad-plus"]
+//     help = []  # not bound to anything
+//
+// Every action that isn't in the file keeps its default keys.
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    Start,
+    Pause,
+    Step,
+    CycleOnStable,
+    ToggleGrid,
+    Clear,
+    Reset,
+    ToggleHud,
+    ToggleTrails,
+    ColourByAlive,
+    ColourByAge,
+    ColourByBirthsDeaths,
+    ColourByActivity,
+    CycleDrawState,
+    CycleTheme,
+    Save,
+    Load,
+    ExportStats,
+    KeyboardCursor,
+    ToggleHelp,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    Fit,
+    CursorLeft,
+    CursorRight,
+    CursorUp,
+    CursorDown,
+    CursorToggle,
+    CursorClear,
+}
+
+/// When an action can happen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Always,
+    // Only on the infinite plane
+    Plane,
+    // Only while editing with the keyboard cursor, which takes over any key
+    // it uses (so its keys can be the same as the ones for other actions)
+    Cursor,
+}
+
+/// A key, with the modifiers that have to be held down with it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub key: Key,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    // In the order they're listed in the help
+    pub bindings: Vec<(Action, Vec<Binding>)>,
+}
+
+pub const ACTIONS: [Action; 33] = [
+    Action::Start,
+    Action::Pause,
+    Action::Step,
+    Action::CycleOnStable,
+    Action::Reset,
+    Action::Clear,
+    Action::ToggleGrid,
+    Action::ToggleHud,
+    Action::ToggleTrails,
+    Action::ColourByAlive,
+    Action::ColourByAge,
+    Action::ColourByBirthsDeaths,
+    Action::ColourByActivity,
+    Action::CycleDrawState,
+    Action::CycleTheme,
+    Action::Save,
+    Action::Load,
+    Action::ExportStats,
+    Action::KeyboardCursor,
+    Action::ToggleHelp,
+    Action::PanLeft,
+    Action::PanRight,
+    Action::PanUp,
+    Action::PanDown,
+    Action::ZoomIn,
+    Action::ZoomOut,
+    Action::Fit,
+    Action::CursorLeft,
+    Action::CursorRight,
+    Action::CursorUp,
+    Action::CursorDown,
+    Action::CursorToggle,
+    Action::CursorClear,
+];
+
+// The names keys go by in keymap files (the first one is also how they're
+// shown), on top of the letters, digits and F1 to F12
+const KEY_NAMES: [(&str, Key); 33] = [
+    ("left", Key::Left),
+    ("right", Key::Right),
+    ("up", Key::Up),
+    ("down", Key::Down),
+    ("space", Key::Space),
+    ("enter", Key::Return),
+    ("return", Key::Return),
+    ("backspace", Key::Back),
+    ("delete", Key::Delete),
+    ("tab", Key::Tab),
+    ("escape", Key::Escape),
+    ("insert", Key::Insert),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("pageup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+    ("=", Key::Equals),
+    ("+", Key::Plus),
+    ("-", Key::Minus),
+    ("numpad-plus", Key::NumpadAdd),
+    ("numpad-minus", Key::NumpadSubtract),
+    (",", Key::Comma),
+    (".", Key::Period),
+    ("/", Key::Slash),
+    ("\\", Key::Backslash),
+    (";", Key::Semicolon),
+    ("'", Key::Apostrophe),
+    ("[", Key::LBracket),
+    ("]", Key::RBracket),
+    ("`", Key::Grave),
+    ("equals", Key::Equals),
+    ("plus", Key::Plus),
+    ("minus", Key::Minus),
+];
+
+const LETTERS: [Key; 26] = [
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+];
+
+const DIGITS: [Key; 10] = [
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+];
+
+const FUNCTION_KEYS: [Key; 12] = [
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+];
+
+// Functions
+// ----------------------------------------------------------------------------
+
+/// The key with the given name (case doesn't matter), eg. "s", "F1", "left" or "+".
+pub fn key_from_name(name: &str) -> Option<Key> {
+    let lower = name.trim().to_lowercase();
+    let mut chars = lower.chars();
+
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_lowercase() {
+            return Some(LETTERS[(c as u8 - b'a') as usize]);
+        }
+        if let Some(digit) = c.to_digit(10) {
+            return Some(DIGITS[digit as usize]);
+        }
+    }
+
+    if let Some(number) = lower
+        .strip_prefix('f')
+        .and_then(|n| n.parse::<usize>().ok())
+    {
+        if (1..=FUNCTION_KEYS.len()).contains(&number) {
+            return Some(FUNCTION_KEYS[number - 1]);
+        }
+    }
+
+    KEY_NAMES
+        .iter()
+        .find(|(key_name, _)| *key_name == lower)
+        .map(|(_, key)| *key)
+}
+
+/// How the key is written in keymap files and in the help.
+pub fn key_name(key: Key) -> String {
+    if let Some(i) = LETTERS.iter().position(|k| *k == key) {
+        return ((b'A' + i as u8) as char).to_string();
+    }
+    if let Some(i) = DIGITS.iter().position(|k| *k == key) {
+        return i.to_string();
+    }
+    if let Some(i) = FUNCTION_KEYS.iter().position(|k| *k == key) {
+        return format!("F{}", i + 1);
+    }
+
+    match KEY_NAMES.iter().find(|(_, k)| *k == key) {
+        Some((name, _)) => name.to_string(),
+        None => format!("{key:?}"),
+    }
+}
+
+impl Action {
+    /// What the action is called in keymap files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Start => "start",
+            Action::Pause => "pause",
+            Action::Step => "step",
+            Action::CycleOnStable => "on-stable",
+            Action::ToggleGrid => "grid",
+            Action::Clear => "clear",
+            Action::Reset => "reset",
+            Action::ToggleHud => "hud",
+            Action::ToggleTrails => "trails",
+            Action::ColourByAlive => "colour-alive",
+            Action::ColourByAge => "colour-age",
+            Action::ColourByBirthsDeaths => "colour-births-deaths",
+            Action::ColourByActivity => "colour-activity",
+            Action::CycleDrawState => "draw-state",
+            Action::CycleTheme => "theme",
+            Action::Save => "save",
+            Action::Load => "load",
+            Action::ExportStats => "export-stats",
+            Action::KeyboardCursor => "keyboard-cursor",
+            Action::ToggleHelp => "help",
+            Action::PanLeft => "pan-left",
+            Action::PanRight => "pan-right",
+            Action::PanUp => "pan-up",
+            Action::PanDown => "pan-down",
+            Action::ZoomIn => "zoom-in",
+            Action::ZoomOut => "zoom-out",
+            Action::Fit => "fit",
+            Action::CursorLeft => "cursor-left",
+            Action::CursorRight => "cursor-right",
+            Action::CursorUp => "cursor-up",
+            Action::CursorDown => "cursor-down",
+            Action::CursorToggle => "cursor-toggle",
+            Action::CursorClear => "cursor-clear",
+        }
+    }
+
+    /// What the action does, for the help.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Start => "Start the simulation",
+            Action::Pause => "Pause/resume the simulation",
+            Action::Step => "Step one generation at a time",
+            Action::CycleOnStable => "Cycle what happens once the board stabilises",
+            Action::ToggleGrid => "Toggle the grid",
+            Action::Clear => "Clear all cells",
+            Action::Reset => "Reset the simulation",
+            Action::ToggleHud => "Toggle the HUD, messages and population plot",
+            Action::ToggleTrails => "Toggle the trails of moving objects",
+            Action::ColourByAlive => "Colour the cells by alive/dead",
+            Action::ColourByAge => "Colour the cells by age",
+            Action::ColourByBirthsDeaths => "Colour the cells by births/deaths",
+            Action::ColourByActivity => "Colour the cells by activity",
+            Action::CycleDrawState => "Cycle the state the left button draws",
+            Action::CycleTheme => "Cycle the colour themes",
+            Action::Save => "Write the board to the pattern file",
+            Action::Load => "Load the board from the pattern file",
+            Action::ExportStats => "Export the per-generation statistics",
+            Action::KeyboardCursor => "Edit with the keyboard cursor",
+            Action::ToggleHelp => "Toggle this help",
+            Action::PanLeft => "Move the view left",
+            Action::PanRight => "Move the view right",
+            Action::PanUp => "Move the view up",
+            Action::PanDown => "Move the view down",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::Fit => "Fit the whole pattern in the window",
+            Action::CursorLeft => "Move the cursor left (shift selects)",
+            Action::CursorRight => "Move the cursor right (shift selects)",
+            Action::CursorUp => "Move the cursor up (shift selects)",
+            Action::CursorDown => "Move the cursor down (shift selects)",
+            Action::CursorToggle => "Toggle the selected cells",
+            Action::CursorClear => "Clear the selected cells",
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        match self {
+            Action::PanLeft
+            | Action::PanRight
+            | Action::PanUp
+            | Action::PanDown
+            | Action::ZoomIn
+            | Action::ZoomOut
+            | Action::Fit => Mode::Plane,
+            Action::CursorLeft
+            | Action::CursorRight
+            | Action::CursorUp
+            | Action::CursorDown
+            | Action::CursorToggle
+            | Action::CursorClear => Mode::Cursor,
+            _ => Mode::Always,
+        }
+    }
+
+    // The keys it has out of the box
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Start => &["s"],
+            Action::Pause => &["p"],
+            Action::Step => &["n"],
+            Action::CycleOnStable => &["a"],
+            Action::ToggleGrid => &["g"],
+            Action::Clear => &["c"],
+            Action::Reset => &["r"],
+            Action::ToggleHud => &["h"],
+            Action::ToggleTrails => &["t"],
+            Action::ColourByAlive => &["1"],
+            Action::ColourByAge => &["2"],
+            Action::ColourByBirthsDeaths => &["3"],
+            Action::ColourByActivity => &["4"],
+            Action::CycleDrawState => &["d"],
+            Action::CycleTheme => &["k"],
+            Action::Save => &["w"],
+            Action::Load => &["l"],
+            Action::ExportStats => &["e"],
+            Action::KeyboardCursor => &["i"],
+            Action::ToggleHelp => &["F1", "shift+/"],
+            Action::PanLeft => &["left"],
+            Action::PanRight => &["right"],
+            Action::PanUp => &["up"],
+            Action::PanDown => &["down"],
+            Action::ZoomIn => &["=", "+", "numpad-plus"],
+            Action::ZoomOut => &["-", "numpad-minus"],
+            Action::Fit => &["f"],
+            Action::CursorLeft => &["left", "h"],
+            Action::CursorRight => &["right", "l"],
+            Action::CursorUp => &["up", "k"],
+            Action::CursorDown => &["down", "j"],
+            Action::CursorToggle => &["space"],
+            Action::CursorClear => &["delete", "backspace"],
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ACTIONS
+            .iter()
+            .find(|action| action.name() == s.trim())
+            .copied()
+            .ok_or_else(|| Error::Invalid(format!("Unknown action '{s}'")))
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Binding {
+    /// The key as it was just pressed.
+    pub fn pressed(key: Key, mods: ModifiersState) -> Self {
+        Binding {
+            key,
+            ctrl: mods.ctrl(),
+            alt: mods.alt(),
+            shift: mods.shift(),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = Error;
+
+    /// Eg. "s", "ctrl+s", "shift+F1", or "+" (and "ctrl++") for the plus key.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (modifiers, key) = match s.trim().strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => match s.trim().rsplit_once('+') {
+                Some((modifiers, key)) if !key.is_empty() => (modifiers, key),
+                _ => ("", s.trim()),
+            },
+        };
+
+        let mut binding = Binding {
+            key: key_from_name(key)
+                .ok_or_else(|| Error::Invalid(format!("Unknown key '{key}' in '{s}'")))?,
+            ctrl: false,
+            alt: false,
+            shift: false,
+        };
+
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier.trim().to_lowercase().as_str() {
+                "ctrl" | "control" => binding.ctrl = true,
+                "alt" => binding.alt = true,
+                "shift" => binding.shift = true,
+                _ => {
+                    return Err(Error::Invalid(format!(
+                        "Unknown modifier '{modifier}' in '{s}'"
+                    )))
+                }
+            }
+        }
+
+        Ok(binding)
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "alt+")?;
+        }
+        if self.shift {
+            write!(f, "shift+")?;
+        }
+        write!(f, "{}", key_name(self.key))
+    }
+}
+
+// Whether the two actions could be waiting for the same key at the same time
+fn can_clash(a: Action, b: Action) -> bool {
+    (a.mode() == Mode::Cursor) == (b.mode() == Mode::Cursor)
+}
+
+impl Keymap {
+    /// Every binding used by more than one action that can happen at the same time.
+    pub fn conflicts(&self) -> Vec<(Binding, Action, Action)> {
+        let mut conflicts = Vec::new();
+
+        for (i, (action, bindings)) in self.bindings.iter().enumerate() {
+            for (other, other_bindings) in &self.bindings[i + 1..] {
+                if !can_clash(*action, *other) {
+                    continue;
+                }
+                for binding in bindings.iter().filter(|b| other_bindings.contains(b)) {
+                    conflicts.push((*binding, *action, *other));
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// What the key does, given where we are (on the plane, editing with the
+    /// cursor..). Holding shift down on top of the key's modifiers only counts
+    /// if there's a binding for it, otherwise it's like the key without shift.
+    pub fn action_for(&self, pressed: Binding, plane: bool, cursor: bool) -> Option<Action> {
+        let active = |action: &Action| match action.mode() {
+            Mode::Always => true,
+            Mode::Plane => plane,
+            Mode::Cursor => cursor,
+        };
+        let find = |pressed: Binding| {
+            // The cursor takes over the keys it uses
+            self.bindings
+                .iter()
+                .filter(|(action, bindings)| active(action) && bindings.contains(&pressed))
+                .map(|(action, _)| *action)
+                .min_by_key(|action| action.mode() != Mode::Cursor)
+        };
+
+        find(pressed).or_else(|| {
+            let unshifted = Binding {
+                shift: false,
+                ..pressed
+            };
+            find(unshifted).filter(|_| pressed.shift)
+        })
+    }
+
+    /// The keys of the action.
+    pub fn keys(&self, action: Action) -> &[Binding] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(&[], |(_, bindings)| bindings.as_slice())
+    }
+
+    /// One line per action that has keys, like "S, ctrl+s  Start the simulation",
+    /// leaving out the ones for the plane unless we're on it.
+    pub fn help(&self, plane: bool) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(action, bindings)| {
+                !bindings.is_empty() && (plane || action.mode() != Mode::Plane)
+            })
+            .map(|(action, bindings)| {
+                let keys: Vec<String> = bindings.iter().map(|b| b.to_string()).collect();
+                format!("{:<16}{}", keys.join(", "), action.description())
+            })
+            .collect()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: ACTIONS
+                .iter()
+                .map(|action| {
+                    let bindings = action
+                        .default_keys()
+                        .iter()
+                        .map(|key| key.parse().unwrap())
+                        .collect();
+                    (*action, bindings)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The default keymap, with the actions in the TOML text bound to their keys instead.
+pub fn parse_keymap(text: &str) -> Result<Keymap, Error> {
+    let table: toml::value::Table = toml::from_str(text)?;
+    let mut keymap = Keymap::default();
+
+    for (name, value) in table {
+        let action: Action = name.parse()?;
+
+        let keys = match value {
+            toml::Value::String(key) => vec![key],
+            toml::Value::Array(keys) => keys
+                .into_iter()
+                .map(|key| match key {
+                    toml::Value::String(key) => Ok(key),
+                    other => Err(format!("Expected a key for '{name}', found {other}")),
+                })
+                .collect::<Result<_, _>>()?,
+            other => {
+                let message = format!("Expected a key for '{name}', found {other}");
+                return Err(Error::Invalid(message));
+            }
+        };
+        let bindings = keys
+            .iter()
+            .map(|key| key.parse())
+            .collect::<Result<Vec<Binding>, _>>()?;
+
+        for (a, b) in keymap.bindings.iter_mut() {
+            if *a == action {
+                *b = bindings.clone();
+            }
+        }
+    }
+
+    let conflicts = keymap.conflicts();
+    if !conflicts.is_empty() {
+        let lines: Vec<String> = conflicts
+            .iter()
+            .map(|(binding, a, b)| format!("'{binding}' is bound to both '{a}' and '{b}'"))
+            .collect();
+        return Err(Error::Invalid(lines.join("\n")));
+    }
+
+    Ok(keymap)
+}
+
+pub fn load_keymap<P: AsRef<Path>>(path: P) -> Result<Keymap, Error> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+
+    parse_keymap(&text).map_err(|e| e.in_file(path))
+}
@@ -0,0 +1,50 @@
+use log::LevelFilter;
+
+// Everything worth knowing goes through the `log` macros, under one of these
+// targets, so each can be turned up or down on its own. By default only the
+// `info` messages and above are shown (on stderr), eg. to trace the keys and
+// mouse buttons while keeping the rest quiet:
+//
+//     cargo run -- --log input=trace
+//
+// RUST_LOG works the same way, and `--log` wins when both are given.
+
+// Key presses, mouse buttons and strokes
+pub const INPUT: &str = "input";
+// Generations, stabilisation, moving objects, soups
+pub const SIMULATION: &str = "simulation";
+// Patterns, statistics and other files
+pub const IO: &str = "io";
+// The window, the board layout, what gets drawn
+pub const RENDER: &str = "render";
+
+// Functions
+// ----------------------------------------------------------------------------
+
+/// The level for a number of -v (positive) or -q (negative) flags.
+pub fn level_for(verbosity: i32) -> LevelFilter {
+    match verbosity {
+        i32::MIN..=-2 => LevelFilter::Error,
+        -1 => LevelFilter::Warn,
+        0 => LevelFilter::Info,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Send the log messages to stderr, at the given level unless RUST_LOG
+/// or `filters` (like "simulation=debug,input=trace") say otherwise.
+pub fn init(level: LevelFilter, filters: Option<&str>) {
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(level).format_timestamp(None);
+
+    if let Ok(env_filters) = std::env::var("RUST_LOG") {
+        builder.parse_filters(&env_filters);
+    }
+    if let Some(filters) = filters {
+        builder.parse_filters(filters);
+    }
+
+    // Only fails if there's a logger already, eg. when called twice
+    let _ = builder.try_init();
+}
@@ -1,46 +1,354 @@
-mod core;
-
+use game_of_life::active::ActiveRegion;
+use game_of_life::cursor::Cursor;
+use game_of_life::detect::StabilisationReport;
+use game_of_life::error::Error;
+use game_of_life::geometry::{Geometry, Layout};
+use game_of_life::keymap::{Action, Binding};
+use game_of_life::plane::{self, Camera, Plane};
+use game_of_life::{cli, core, detect, headless, logging, rle, rules, stats, tracking};
+use log::Level;
 use nannou::prelude::*;
 
+const STATS_CSV_PATH: &str = "stats.csv";
+// Where 'W' and 'L' write and load the board, unless --pattern says otherwise
+const DEFAULT_PATTERN_PATH: &str = "pattern.rle";
+
 fn main() {
+    let options = match cli::options_from_env() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    logging::init(options.log_level, options.log_filters.as_deref());
+
+    if options.show_help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    if options.headless {
+        if let Err(e) = headless::run(&options) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     nannou::app(model).update(update).view(view).run();
 }
 
-fn key_pressed(_app: &App, model: &mut core::Model, key: Key) {
-    //println!("Key pressed: {:?}", key);
+fn reset(model: &mut core::Model) {
+    model.current_stroke = Vec::new();
+    model.state = core::AppState::Init;
+
+    if model.plane.is_some() {
+        model.plane = Some(Plane::random(model.num_cells_x, model.num_cells_y));
+    } else {
+        let cells = core::initial_cells(
+            &model.rule,
+            &model.initial_row,
+            model.num_cells_x,
+            model.num_cells_y,
+        );
+        model.cells = cells;
+    }
+    clear_history(model);
+}
 
-    match key {
-        // Start
-        Key::S => {
-            println!("User pressed 'S' for 'Start'.");
-            model.state = core::AppState::Running;
+// Show what just happened on top of the board for a few seconds (and log it)
+fn notify(app: &App, model: &mut core::Model, level: Level, target: &str, message: String) {
+    log::log!(target: target, level, "{message}");
+    model.messages.push((message, level, app.time));
+    if model.messages.len() > core::MAX_MESSAGES {
+        model.messages.remove(0);
+    }
+}
+
+// Forget everything we learned about the previous generations
+fn clear_history(model: &mut core::Model) {
+    model.generations = 0;
+    model.stats.clear();
+    model.detector.reset();
+    model.stabilisation = None;
+    model.tracker.reset();
+    // The whole board may be new
+    model.active.reset();
+}
+
+// The layout (and grid) depends on the geometry of the rule
+fn set_rule(model: &mut core::Model, rule: rules::Rule) {
+    let geometry = Geometry::of(&rule.neighbourhood);
+    model.rule = rule;
+    // What could change depends on the rule
+    model.active.reset();
+    if model.draw_state >= model.rule.states {
+        model.draw_state = rules::ALIVE;
+    }
+
+    if geometry != model.layout.geometry {
+        model.layout = Layout::new(
+            geometry,
+            model.app_width,
+            model.app_height,
+            model.num_cells_x,
+            model.num_cells_y,
+            model.cell_size,
+        );
+        model.lines = core::create_grid_for(&model.layout);
+    }
+}
+
+fn load_pattern(app: &App, model: &mut core::Model) {
+    match rle::load_rle(&model.pattern_path) {
+        Ok(pattern) => {
+            if let Some(rule) = pattern.rule.clone() {
+                if let (Some(_), Err(e)) = (&model.plane, plane::check_rule(&rule)) {
+                    let message = format!("Failed to load the pattern: {e}");
+                    notify(app, model, Level::Warn, logging::IO, message);
+                    return;
+                }
+                set_rule(model, rule);
+            }
+
+            let mut clipped = 0;
+            if model.plane.is_some() {
+                let plane = Plane::from_pattern(&pattern);
+                if let Some(bounds) = plane.bounds() {
+                    model.camera.fit(bounds, model.app_width, model.app_height);
+                }
+                model.plane = Some(plane);
+            } else {
+                let (cells, outside) =
+                    rle::centred_pattern(&pattern, model.num_cells_x, model.num_cells_y);
+                model.cells = cells;
+                clipped = outside;
+            }
+            model.state = core::AppState::Init;
+            clear_history(model);
+
+            let loaded = format!("Loaded {} ({})", model.pattern_path, model.rule);
+            notify(app, model, Level::Info, logging::IO, loaded);
+            if clipped > 0 {
+                let message = format!("{clipped} cells of the pattern didn't fit on the board");
+                notify(app, model, Level::Warn, logging::IO, message);
+            }
         }
-        // Toggle grid
-        Key::G => {
-            println!("User pressed 'G' to toggle the grid.");
-            model.should_draw_grid = !model.should_draw_grid;
+        Err(e) => {
+            let message = format!("Failed to load the pattern: {e}");
+            notify(app, model, Level::Warn, logging::IO, message);
         }
-        // Clear
-        Key::C => {
-            println!("User pressed 'C' to clear the cells.");
-            model.cells.rows = core::get_all_cells_as_dead();
+    }
+}
+
+fn set_render_mode(app: &App, model: &mut core::Model, render_mode: core::RenderMode) {
+    let message = format!("Colouring the cells by {render_mode:?}");
+    notify(app, model, Level::Info, logging::RENDER, message);
+    model.render_mode = render_mode;
+}
+
+// Move the keyboard cursor and edit the cells under it
+fn cursor_action(app: &App, model: &mut core::Model, action: Action) {
+    let cursor = match &mut model.cursor {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    let (dx, dy) = match action {
+        Action::CursorLeft => (-1, 0),
+        Action::CursorRight => (1, 0),
+        Action::CursorUp => (0, -1),
+        Action::CursorDown => (0, 1),
+        // Same as with the mouse, only before the simulation starts
+        _ => {
+            if let core::AppState::Init = model.state {
+                match action {
+                    Action::CursorClear => cursor.fill(&mut model.cells, rules::DEAD),
+                    _ => cursor.toggle(&mut model.cells, model.draw_state),
+                }
+                let (min_x, min_y, max_x, max_y) = cursor.selection();
+                for x in min_x..=max_x {
+                    for y in min_y..=max_y {
+                        model.active.edited(x, y);
+                    }
+                }
+            }
+            return;
         }
-        // Reset
-        Key::R => {
-            println!("User pressed 'R' for 'Reset'.");
+    };
 
-            model.current_stroke = Vec::new();
-            model.state = core::AppState::Init;
+    let select = app.keys.mods.shift();
+    cursor.move_by(dx, dy, model.num_cells_x, model.num_cells_y, select);
+}
 
-            let cells = core::init_cells(model.num_cells_x, model.num_cells_y, true);
-            model.cells = cells;
+fn key_pressed(app: &App, model: &mut core::Model, key: Key) {
+    let pressed = Binding::pressed(key, app.keys.mods);
+    let action = model
+        .keymap
+        .action_for(pressed, model.plane.is_some(), model.cursor.is_some());
+    let action = match action {
+        Some(action) => action,
+        None => {
+            log::trace!(target: logging::INPUT, "Key pressed: {pressed} (not bound)");
+            return;
         }
-        _ => {}
+    };
+    log::debug!(target: logging::INPUT, "Key pressed: {pressed} ({action})");
+
+    match action {
+        Action::Start => {
+            model.state = core::AppState::Running;
+            // Out of the way of the board
+            model.should_draw_help = false;
+        }
+        Action::Pause => {
+            model.state = match model.state {
+                core::AppState::Running => core::AppState::Paused,
+                core::AppState::Paused => core::AppState::Running,
+                core::AppState::Init => core::AppState::Init,
+            };
+        }
+        // One generation, then wait for the next key
+        Action::Step => {
+            if let core::AppState::Running = model.state {
+                return;
+            }
+            advance(app, model);
+            if let core::AppState::Init = model.state {
+                model.state = core::AppState::Paused;
+            }
+        }
+        // Cycle what happens once the board has stabilised
+        Action::CycleOnStable => {
+            model.on_stable = model.on_stable.next();
+            let message = format!("Once stable, {}", model.on_stable);
+            notify(app, model, Level::Info, logging::SIMULATION, message);
+        }
+        Action::ToggleGrid => {
+            model.should_draw_grid = !model.should_draw_grid;
+        }
+        Action::Clear => {
+            model.cells.rows = core::get_all_cells_as_dead(model.num_cells_x, model.num_cells_y);
+            if model.plane.is_some() {
+                model.plane = Some(Plane::new());
+            }
+            clear_history(model);
+        }
+        Action::Reset => {
+            reset(model);
+        }
+        // Toggle the HUD and population plot
+        Action::ToggleHud => {
+            model.should_draw_hud = !model.should_draw_hud;
+        }
+        // Toggle the trails of the moving objects
+        Action::ToggleTrails => {
+            model.should_draw_trails = !model.should_draw_trails;
+        }
+        // Colour the cells by..
+        Action::ColourByAlive => set_render_mode(app, model, core::RenderMode::Alive),
+        Action::ColourByAge => set_render_mode(app, model, core::RenderMode::Age),
+        Action::ColourByBirthsDeaths => set_render_mode(app, model, core::RenderMode::BirthsDeaths),
+        Action::ColourByActivity => set_render_mode(app, model, core::RenderMode::Activity),
+        // Cycle the state the left mouse button draws (eg. Wireworld conductors)
+        Action::CycleDrawState => {
+            model.draw_state = model.draw_state % (model.rule.states - 1) + 1;
+            let message = format!("Drawing cells in state {}", model.draw_state);
+            notify(app, model, Level::Info, logging::INPUT, message);
+        }
+        // Cycle the built-in colour themes
+        Action::CycleTheme => {
+            model.theme = model.theme.next_preset();
+            let message = format!("Using the '{}' theme", model.theme.name);
+            notify(app, model, Level::Info, logging::RENDER, message);
+        }
+        // Write the board to an RLE file
+        Action::Save => {
+            let pattern = match &model.plane {
+                Some(plane) => plane.to_pattern(&model.rule),
+                None => rle::pattern_from_cells(
+                    &model.cells,
+                    model.num_cells_x,
+                    model.num_cells_y,
+                    &model.rule,
+                ),
+            };
+            let (level, message) = match rle::save_rle(&pattern, &model.pattern_path) {
+                Ok(()) => (Level::Info, format!("Wrote {}", model.pattern_path)),
+                Err(e) => (Level::Warn, format!("Failed to write the pattern: {e}")),
+            };
+            notify(app, model, level, logging::IO, message);
+        }
+        // Load the board back from the RLE file
+        Action::Load => {
+            load_pattern(app, model);
+        }
+        // Export statistics
+        Action::ExportStats => {
+            let (level, message) = match model.stats.write_csv(STATS_CSV_PATH) {
+                Ok(()) => (
+                    Level::Info,
+                    format!(
+                        "Wrote {} generations to {STATS_CSV_PATH}",
+                        model.stats.entries.len()
+                    ),
+                ),
+                Err(e) => (
+                    Level::Warn,
+                    format!("Failed to write {STATS_CSV_PATH}: {e}"),
+                ),
+            };
+            notify(app, model, level, logging::IO, message);
+        }
+        // Edit the board with the keyboard
+        Action::KeyboardCursor => {
+            if model.plane.is_some() {
+                return;
+            }
+            if model.cursor.take().is_some() {
+                let message = "Back to editing with the mouse".to_string();
+                notify(app, model, Level::Info, logging::INPUT, message);
+            } else {
+                let message = "Editing with the keyboard cursor".to_string();
+                notify(app, model, Level::Info, logging::INPUT, message);
+                model.cursor = Some(Cursor::new(model.num_cells_x / 2, model.num_cells_y / 2));
+            }
+        }
+        Action::ToggleHelp => model.should_draw_help = !model.should_draw_help,
+        // Move around the infinite plane
+        Action::PanLeft => model
+            .camera
+            .pan(-0.25, 0.0, model.app_width, model.app_height),
+        Action::PanRight => model
+            .camera
+            .pan(0.25, 0.0, model.app_width, model.app_height),
+        Action::PanUp => model
+            .camera
+            .pan(0.0, -0.25, model.app_width, model.app_height),
+        Action::PanDown => model
+            .camera
+            .pan(0.0, 0.25, model.app_width, model.app_height),
+        Action::ZoomIn => model.camera.zoom(2.0),
+        Action::ZoomOut => model.camera.zoom(0.5),
+        // Fit the whole pattern in the window
+        Action::Fit => {
+            if let Some(bounds) = model.plane.as_ref().and_then(|p| p.bounds()) {
+                model.camera.fit(bounds, model.app_width, model.app_height);
+            }
+        }
+        Action::CursorLeft
+        | Action::CursorRight
+        | Action::CursorUp
+        | Action::CursorDown
+        | Action::CursorToggle
+        | Action::CursorClear => cursor_action(app, model, action),
     }
 }
 
 fn mouse_pressed(_app: &App, model: &mut core::Model, button: MouseButton) {
-    println!("Mouse pressed: {button:?}");
+    log::debug!(target: logging::INPUT, "Mouse pressed: {button:?}");
     model.drawing_state = core::DrawingState::Started;
     match button {
         MouseButton::Left => model.should_draw_white = true,
@@ -58,42 +366,51 @@ fn mouse_moved(_app: &App, model: &mut core::Model, pos: Point2) {
     if let core::AppState::Init = model.state {
         // Start drawing
         if let core::DrawingState::Started = model.drawing_state {
-            // Snap the point to the grid
-            let snapped = core::snap_to_grid(pos, model);
-
-            // Discard clicks outside the target area
-            if snapped.x.abs() > model.app_width * 0.5 {
+            let state = if model.should_draw_white {
+                model.draw_state
+            } else {
+                rules::DEAD
+            };
+
+            if let Some(plane) = &mut model.plane {
+                let (x, y) = model.camera.to_cell(pos);
+                log::trace!(target: logging::INPUT, "Drawing cell {x},{y} in state {state}");
+                plane.set(x, y, state);
                 return;
             }
-            if snapped.y.abs() > model.app_height * 0.5 {
-                return;
+
+            // Clicks outside of the board are discarded (the window can be
+            // bigger than the board, and hexagons don't line up with the grid)
+            if let Some((x, y)) = model.layout.cell_at(pos) {
+                log::trace!(target: logging::INPUT, "Drawing cell {x},{y} in state {state}");
+                // Dragging over a cell that's already in that state leaves it be
+                let cell = &mut model.cells.rows[x].values[y];
+                if cell.state != state {
+                    *cell = core::fresh_cell(state);
+                    model.active.edited(x, y);
+                }
             }
+        }
+    }
+}
 
-            // Map the point to a cell in the grid
-            let cell_index_x = map_range(
-                snapped.x,
-                -model.app_width * 0.5,
-                model.app_width * 0.5,
-                0,
-                model.num_cells_x,
-            );
-            let cell_index_y = map_range(
-                snapped.y,
-                -model.app_height * 0.5,
-                model.app_height * 0.5,
-                model.num_cells_y - 1,
-                0,
-            );
+// The board stays as it is (even when minimised), only what's shown of it changes
+fn resized(_app: &App, model: &mut core::Model, size: Vec2) {
+    model.app_width = size.x;
+    model.app_height = size.y;
+    model.grid_points = core::create_grid_points(size.x, size.y, model.cell_size);
 
-            let is_alive = model.should_draw_white;
-            model.cells.rows[cell_index_x].values[cell_index_y].is_alive = is_alive;
-        }
+    // The camera stays on the same spot of the infinite plane
+    if model.plane.is_some() {
+        return;
     }
+
+    model.layout = model.layout.resized(size.x, size.y, model.cell_size);
+    model.lines = core::create_grid_for(&model.layout);
 }
 
-fn mouse_released(_app: &App, model: &mut core::Model, _button: MouseButton) {
-    //
-    //println!("Mouse released");
+fn mouse_released(_app: &App, model: &mut core::Model, button: MouseButton) {
+    log::debug!(target: logging::INPUT, "Mouse released: {button:?}");
 
     if let core::DrawingState::Started = model.drawing_state {
         model.drawing_state = core::DrawingState::Ended;
@@ -101,53 +418,111 @@ fn mouse_released(_app: &App, model: &mut core::Model, _button: MouseButton) {
 }
 
 fn model(app: &App) -> core::Model {
-    // Set up the window
-    app.new_window()
+    // Already validated in main(), before the window was opened
+    let options = cli::options_from_env().unwrap_or_default();
+
+    // Set up the window, with room for the board (which then follows the window size)
+    let (board_width, board_height) = options.size;
+    let window = app
+        .new_window()
         .title("Game of Life")
         .key_pressed(key_pressed)
         .mouse_pressed(mouse_pressed)
         .mouse_moved(mouse_moved)
         .mouse_released(mouse_released)
-        .size(512, 512)
-        .build()
-        .unwrap();
-
-    //app.main_window().set_resizable(false);
+        .resized(resized)
+        .size(
+            (board_width * core::CELL_SIZE) as u32,
+            (board_height * core::CELL_SIZE) as u32,
+        )
+        .build();
+    // Nothing to show the error in without a window
+    if let Err(e) = window {
+        log::error!(target: logging::RENDER, "{}", Error::Render(e.to_string()));
+        std::process::exit(1);
+    }
 
     let window_rect = app.window_rect();
     let width = window_rect.w();
     let height = window_rect.h();
 
-    let num_cells_x = width as i32 / core::CELL_SIZE as i32;
-    let num_cells_y = height as i32 / core::CELL_SIZE as i32;
-
-    // Initialize all of the cells
-    let cells = core::init_cells(num_cells_x as usize, num_cells_y as usize, true);
+    let (num_cells_x, num_cells_y) = core::board_size(width, height, core::CELL_SIZE);
+
+    // Anything that went wrong on the way, shown once the window is up
+    let mut messages = Vec::new();
+
+    // Initialize all of the cells, from the pattern if one was given
+    let pattern_path = options
+        .pattern
+        .clone()
+        .unwrap_or_else(|| DEFAULT_PATTERN_PATH.to_string());
+    let pattern = match &options.pattern {
+        Some(path) => match rle::load_rle(path) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                let message = format!("Failed to load the pattern: {e}");
+                log::warn!(target: logging::IO, "{message}");
+                messages.push((message, Level::Warn, app.time));
+                None
+            }
+        },
+        None => None,
+    };
+    let rule = options.rule_for(pattern.as_ref());
+
+    let cells = match &pattern {
+        Some(pattern) => {
+            let (cells, clipped) = rle::centred_pattern(pattern, num_cells_x, num_cells_y);
+            if clipped > 0 {
+                let message = format!("{clipped} cells of the pattern didn't fit on the board");
+                log::warn!(target: logging::IO, "{message}");
+                messages.push((message, Level::Warn, app.time));
+            }
+            cells
+        }
+        None => core::initial_cells(&rule, &options.initial_row, num_cells_x, num_cells_y),
+    };
 
     // Calculate the integers that make up the grid
-    let w = (width) as i32;
-    let h = (height) as i32;
-
-    let mut grid_points = Vec::new();
-
-    for y in (-h..h).step_by(core::CELL_SIZE) {
-        for x in (-w..w).step_by(core::CELL_SIZE) {
-            grid_points.push(pt2(x as f32, y as f32));
+    let grid_points = core::create_grid_points(width, height, core::CELL_SIZE);
+
+    // Where the cells go on screen, and the lines that make up the grid
+    let layout = Layout::new(
+        Geometry::of(&rule.neighbourhood),
+        width,
+        height,
+        num_cells_x,
+        num_cells_y,
+        core::CELL_SIZE,
+    );
+    let lines = core::create_grid_for(&layout);
+
+    // On the infinite plane the camera starts on the pattern (or soup)
+    let plane = match (options.infinite, plane::check_rule(&rule)) {
+        (false, _) => None,
+        (true, Ok(())) => Some(match &pattern {
+            Some(pattern) => Plane::from_pattern(pattern),
+            None => Plane::random(num_cells_x, num_cells_y),
+        }),
+        (true, Err(e)) => {
+            let message = format!("{e}, using the fixed size board instead");
+            log::warn!(target: logging::SIMULATION, "{message}");
+            messages.push((message, Level::Warn, app.time));
+            None
         }
+    };
+    let mut camera = Camera::new(core::CELL_SIZE as f32);
+    if let (Some(_), Some(bounds)) = (&pattern, plane.as_ref().and_then(|p| p.bounds())) {
+        camera.fit(bounds, width, height);
     }
 
-    // Create the lines that make up the grid
-    let lines = core::create_grid(app, core::CELL_SIZE);
-
-    println!("Canvas size is {width}x{height}");
-    println!("Cell size is {}", core::CELL_SIZE);
-
-    println!("INSTRUCTIONS:");
-    println!("Draw cells with the mouse left (alive) or right (dead) button");
-    println!("Press 'G' to toggle the grid view.");
-    println!("Press 'S' to start the simulation.");
-    println!("Press 'R' to reset the simulation.");
-    println!("Press 'C' to clear all cells (set all cells to dead).");
+    log::info!(target: logging::RENDER, "Canvas size is {width}x{height}");
+    log::info!(
+        target: logging::RENDER,
+        "Board is {num_cells_x}x{num_cells_y} cells of {} pixels",
+        core::CELL_SIZE
+    );
+    log::info!(target: logging::SIMULATION, "Rule is {rule}, on a {} grid", layout.geometry);
 
     core::Model {
         lines,
@@ -155,59 +530,210 @@ fn model(app: &App) -> core::Model {
         cell_size: core::CELL_SIZE,
         app_width: width,
         app_height: height,
-        num_cells_x: num_cells_x as usize,
-        num_cells_y: num_cells_y as usize,
+        num_cells_x,
+        num_cells_y,
         state: core::AppState::Init,
         should_draw_grid: false,
         should_draw_white: true,
+        draw_state: rules::ALIVE,
         drawing_state: core::DrawingState::Void,
         current_stroke: Vec::new(),
         grid_points,
         last_mouse_pos: pt2(0.0, 0.0),
         closest_points: Vec::new(),
         generations: 0,
+        stats: stats::StatsHistory::new(),
+        should_draw_hud: !options.no_hud,
+        last_generation_time: 0.0,
+        generations_per_second: 0.0,
+        detector: detect::CycleDetector::default(),
+        stabilisation: None,
+        on_stable: options.on_stable,
+        tracker: tracking::Tracker::new(),
+        should_draw_trails: false,
+        render_mode: core::RenderMode::Alive,
+        theme: options.theme,
+        rule,
+        layout,
+        initial_row: options.initial_row,
+        threads: options.threads,
+        active: ActiveRegion::new(),
+        plane,
+        camera,
+        cursor: None,
+        pattern_path,
+        keymap: options.keymap,
+        // The keys are on screen until the simulation starts
+        should_draw_help: !options.no_hud,
+        messages,
+    }
+}
+
+// Feed the current generation to the cycle detector
+fn observe(model: &mut core::Model) -> Option<StabilisationReport> {
+    match &model.plane {
+        Some(plane) => {
+            model
+                .detector
+                .observe_hash(model.generations, plane.hash(), plane.cells.is_empty())
+        }
+        None => model.detector.observe(
+            model.generations,
+            &model.cells,
+            model.num_cells_x,
+            model.num_cells_y,
+        ),
+    }
+}
+
+// Step the board (or plane) by one generation, and keep the statistics,
+// the trails and the stabilisation check up to date
+fn advance(app: &App, model: &mut core::Model) {
+    // Record the starting board, so the series begins at generation 0
+    if model.stats.entries.is_empty() {
+        model.stats.record(match &model.plane {
+            Some(plane) => plane.stats(model.generations, None),
+            None => stats::compute_stats(
+                model.generations,
+                None,
+                &model.cells,
+                model.num_cells_x,
+                model.num_cells_y,
+            ),
+        });
+        model.stabilisation = observe(model);
+    }
+
+    if let Some(plane) = model.plane.take() {
+        let next = plane.step(&model.rule);
+        model.generations += 1;
+        log::debug!(target: logging::SIMULATION, "Generation: {}", model.generations);
+
+        model
+            .stats
+            .record(next.stats(model.generations, Some(&plane)));
+        model.plane = Some(next);
+    } else {
+        let previous = model.cells.clone();
+        core::game_of_life(model);
+        model.generations += 1;
+        log::debug!(target: logging::SIMULATION, "Generation: {}", model.generations);
+
+        model.stats.record(stats::compute_stats(
+            model.generations,
+            Some(&previous),
+            &model.cells,
+            model.num_cells_x,
+            model.num_cells_y,
+        ));
+
+        let new_objects = model.tracker.observe(
+            model.generations,
+            &model.cells,
+            model.num_cells_x,
+            model.num_cells_y,
+        );
+        for index in new_objects {
+            let object = &model.tracker.objects[index];
+            let message = format!("Moving object #{}: {}", object.id, object.velocity);
+            notify(app, model, Level::Info, logging::SIMULATION, message);
+        }
+    }
+
+    // Smooth the rate a bit, otherwise the HUD is unreadable
+    let elapsed = app.time - model.last_generation_time;
+    if elapsed > 0.0 {
+        model.generations_per_second = model.generations_per_second * 0.9 + (1.0 / elapsed) * 0.1;
+    }
+    model.last_generation_time = app.time;
+
+    // Check if the board has settled down (only the first time)
+    if model.stabilisation.is_none() {
+        model.stabilisation = observe(model);
+
+        if let Some(report) = model.stabilisation {
+            let message = format!("Stabilised: {report}");
+            notify(app, model, Level::Info, logging::SIMULATION, message);
+
+            match model.on_stable {
+                detect::StableAction::Continue => {}
+                detect::StableAction::Pause => model.state = core::AppState::Paused,
+                detect::StableAction::Reset => {
+                    reset(model);
+                    model.state = core::AppState::Running;
+                }
+            }
+        }
     }
 }
 
 fn update(app: &App, model: &mut core::Model, _update: Update) {
-    if app.elapsed_frames() % 5 != 0 {
+    if !app.elapsed_frames().is_multiple_of(5) {
         return;
     }
 
+    model
+        .messages
+        .retain(|(_, _, time)| app.time - time < core::MESSAGE_SECONDS);
+
     // Do the game of life only when needed
     if let core::AppState::Running = model.state {
-        core::game_of_life(model);
-        model.generations += 1;
-        println!("Generation: {}", model.generations);
+        advance(app, model);
     }
 }
 
 fn view(app: &App, model: &core::Model, frame: Frame) {
     let canvas = app.draw();
-    canvas.background().color(BLACK);
+    canvas.background().color(model.theme.background);
 
-    if app.elapsed_frames() % 5 != 0 {
+    if !app.elapsed_frames().is_multiple_of(5) {
         return;
     }
 
-    // Draw the cells
-    for (i, cell_row) in model.cells.rows.iter().enumerate() {
-        for (j, cell_value) in cell_row.values.iter().enumerate() {
-            core::draw_cell(i, j, &cell_value.is_alive, model, &canvas);
+    // The infinite plane brings its own grid (and isn't tracked, so has no trails)
+    if let Some(plane) = &model.plane {
+        core::draw_plane(plane, model, &canvas);
+    } else {
+        // Draw the cells
+        core::draw_cells(model, &canvas);
+
+        // Draw the grid (if requested)
+        if model.should_draw_grid {
+            for line in model.lines.iter() {
+                canvas
+                    .line()
+                    .start(pt2(line.start_x, line.start_y))
+                    .end(pt2(line.end_x, line.end_y))
+                    .weight(line.weight)
+                    .color(model.theme.grid);
+            }
         }
-    }
 
-    // Draw the grid (if requested)
-    if model.should_draw_grid {
-        for line in model.lines.iter() {
-            canvas
-                .line()
-                .start(pt2(line.start_x, line.start_y))
-                .end(pt2(line.end_x, line.end_y))
-                .weight(line.weight)
-                .color(WHITE);
+        // Draw where the moving objects have been (if requested)
+        if model.should_draw_trails {
+            core::draw_trails(model, &canvas);
         }
+
+        // Draw the keyboard cursor (if editing with it)
+        if let Some(cursor) = &model.cursor {
+            core::draw_cursor(cursor, model, &canvas);
+        }
+    }
+
+    // Draw the statistics (if requested)
+    if model.should_draw_hud {
+        core::draw_hud(model, &canvas);
+        core::draw_population_plot(model, &canvas);
     }
+    core::draw_messages(model, &canvas);
 
-    canvas.to_frame(app, &frame).unwrap();
+    // Draw the key bindings on top of everything (if requested)
+    if model.should_draw_help {
+        core::draw_help(model, &canvas);
+    }
+
+    // A frame that couldn't be drawn is skipped, the next one may work
+    if let Err(e) = canvas.to_frame(app, &frame) {
+        log::error!(target: logging::RENDER, "{}", Error::Render(format!("{e:?}")));
+    }
 }
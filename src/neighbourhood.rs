@@ -0,0 +1,394 @@
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::active::ActiveRegion;
+use crate::core::Cells;
+use crate::error::Error;
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shape {
+    // The square around the cell
+    Moore,
+    // The diamond around the cell
+    VonNeumann,
+    // Hexagons stored on the square grid: the Moore neighbourhood
+    // without the top-right and bottom-left corners (same as Golly)
+    Hexagonal,
+    // Triangles pointing up when x + y is even, down otherwise, and every
+    // triangle touching them (12 at radius 1, which is the only radius)
+    Triangular,
+    // Any set of (dx, dy) offsets
+    Custom(Vec<(i32, i32)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbourhood {
+    pub shape: Shape,
+    pub radius: usize,
+}
+
+// A rectangle of offsets, counted in one go with the summed-area table
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct OffsetBox {
+    dx_min: i32,
+    dx_max: i32,
+    dy_min: i32,
+    dy_max: i32,
+}
+
+/// Prefix sums of the alive cells: any rectangle can be counted with 4 lookups.
+pub struct SummedArea {
+    // The part of the board that was summed, anything outside of it counts as dead
+    xs: Range<usize>,
+    ys: Range<usize>,
+    // (width + 1) * (height + 1), the first row and column are 0
+    sums: Vec<u32>,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+
+/// Whether the triangle of a triangular grid points up (or down).
+pub fn points_up(x: usize, y: usize) -> bool {
+    (x + y).is_multiple_of(2)
+}
+
+impl Neighbourhood {
+    pub fn moore(radius: usize) -> Self {
+        Neighbourhood {
+            shape: Shape::Moore,
+            radius,
+        }
+    }
+
+    pub fn von_neumann(radius: usize) -> Self {
+        Neighbourhood {
+            shape: Shape::VonNeumann,
+            radius,
+        }
+    }
+
+    pub fn hexagonal(radius: usize) -> Self {
+        Neighbourhood {
+            shape: Shape::Hexagonal,
+            radius,
+        }
+    }
+
+    pub fn triangular() -> Self {
+        Neighbourhood {
+            shape: Shape::Triangular,
+            radius: 1,
+        }
+    }
+
+    /// The 8 cells around, what the original Game of Life uses.
+    pub fn is_classic(&self) -> bool {
+        self.shape == Shape::Moore && self.radius == 1
+    }
+
+    /// The (dx, dy) of every neighbour, not including the cell itself.
+    /// For triangles these are the ones of a triangle pointing up, see `offsets_at()`.
+    pub fn offsets(&self) -> Vec<(i32, i32)> {
+        self.offsets_at(0, 0)
+    }
+
+    /// The (dx, dy) of every neighbour of the given cell, not including the cell itself.
+    pub fn offsets_at(&self, x: usize, y: usize) -> Vec<(i32, i32)> {
+        match &self.shape {
+            Shape::Custom(offsets) => {
+                return offsets.iter().copied().filter(|o| *o != (0, 0)).collect();
+            }
+            Shape::Triangular => {
+                // Pointing up, the apex touches 3 triangles above and the base 5 below
+                let (above, below) = if points_up(x, y) { (1, 2) } else { (2, 1) };
+                let mut offsets = Vec::new();
+                offsets.extend((-above..=above).map(|dx| (dx, -1)));
+                offsets.extend([(-2, 0), (-1, 0), (1, 0), (2, 0)]);
+                offsets.extend((-below..=below).map(|dx| (dx, 1)));
+                return offsets;
+            }
+            _ => {}
+        }
+
+        let r = self.radius as i32;
+        let mut offsets = Vec::new();
+
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let included = match self.shape {
+                    Shape::Moore => true,
+                    Shape::VonNeumann => dx.abs() + dy.abs() <= r,
+                    Shape::Hexagonal => (dx - dy).abs() <= r,
+                    Shape::Triangular | Shape::Custom(_) => unreachable!(),
+                };
+                if included && (dx, dy) != (0, 0) {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+
+        offsets
+    }
+
+    /// How many neighbours there are (not including the cell itself).
+    pub fn size(&self) -> usize {
+        self.offsets().len()
+    }
+
+    /// How far the furthest neighbour is, across or down.
+    pub fn reach(&self) -> usize {
+        self.offsets()
+            .iter()
+            .map(|(dx, dy)| dx.unsigned_abs().max(dy.unsigned_abs()) as usize)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // The neighbourhood (including the cell itself) as rectangles:
+    // each row is split in contiguous runs, then equal runs on
+    // consecutive rows are merged (so Moore is a single box)
+    fn boxes(&self) -> Vec<OffsetBox> {
+        let mut offsets = self.offsets();
+        offsets.push((0, 0));
+        offsets.sort_by_key(|(dx, dy)| (*dy, *dx));
+        offsets.dedup();
+
+        let mut runs: Vec<OffsetBox> = Vec::new();
+        for (dx, dy) in offsets {
+            match runs.last_mut() {
+                Some(run) if run.dy_min == dy && run.dx_max + 1 == dx => run.dx_max = dx,
+                _ => runs.push(OffsetBox {
+                    dx_min: dx,
+                    dx_max: dx,
+                    dy_min: dy,
+                    dy_max: dy,
+                }),
+            }
+        }
+
+        let mut boxes: Vec<OffsetBox> = Vec::new();
+        for run in runs {
+            let merged = boxes.iter_mut().rev().find(|b| {
+                b.dx_min == run.dx_min && b.dx_max == run.dx_max && b.dy_max + 1 == run.dy_min
+            });
+            match merged {
+                Some(b) => b.dy_max = run.dy_max,
+                None => boxes.push(run),
+            }
+        }
+
+        boxes
+    }
+}
+
+/// Parse a neighbourhood drawn as text, eg. for von Neumann:
+///
+/// ```text
+/// .x.
+/// x.x
+/// .x.
+/// ```
+///
+/// 'x', 'o', '1' or '#' are neighbours, anything else isn't. The cell itself
+/// is the one in the middle, so there must be an odd number of rows and columns.
+/// Whether the middle counts is up to the rule.
+pub fn parse_mask(text: &str) -> Result<Neighbourhood, Error> {
+    // With their line numbers (from 1), for the errors
+    let numbered: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(number, l)| (number + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty())
+        .collect();
+    let rows: Vec<&str> = numbered.iter().map(|(_, row)| *row).collect();
+
+    let height = rows.len();
+    let width = rows.first().map(|r| r.chars().count()).unwrap_or(0);
+
+    if let Some((number, _)) = numbered.iter().find(|(_, r)| r.chars().count() != width) {
+        return Err(Error::parse(
+            *number,
+            None,
+            "All the rows of the neighbourhood must be as long",
+        ));
+    }
+    if height.is_multiple_of(2) || width.is_multiple_of(2) {
+        return Err(Error::Invalid(format!(
+            "The neighbourhood must have an odd number of rows and columns, got {width}x{height}"
+        )));
+    }
+
+    let (cx, cy) = ((width / 2) as i32, (height / 2) as i32);
+    let mut offsets = Vec::new();
+
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            let offset = (x as i32 - cx, y as i32 - cy);
+            if matches!(c, 'x' | 'o' | '1' | '#') && offset != (0, 0) {
+                offsets.push(offset);
+            }
+        }
+    }
+
+    Ok(Neighbourhood {
+        shape: Shape::Custom(offsets),
+        radius: cx.max(cy) as usize,
+    })
+}
+
+pub fn load_mask<P: AsRef<Path>>(path: P) -> Result<Neighbourhood, Error> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+
+    parse_mask(&text).map_err(|e| e.in_file(path))
+}
+
+impl SummedArea {
+    pub fn new(cells: &Cells, num_cells_x: usize, num_cells_y: usize) -> Self {
+        SummedArea::within(cells, 0..num_cells_x, 0..num_cells_y)
+    }
+
+    /// Same as `new()`, only summing the given part of the board.
+    pub fn within(cells: &Cells, xs: Range<usize>, ys: Range<usize>) -> Self {
+        let stride = ys.len() + 1;
+        let mut sums = vec![0; (xs.len() + 1) * stride];
+
+        for (x, row) in cells.rows[xs.clone()].iter().enumerate() {
+            for (y, cell) in row.values[ys.clone()].iter().enumerate() {
+                let alive = cell.is_alive() as u32;
+                sums[(x + 1) * stride + y + 1] =
+                    alive + sums[x * stride + y + 1] + sums[(x + 1) * stride + y]
+                        - sums[x * stride + y];
+            }
+        }
+
+        SummedArea { xs, ys, sums }
+    }
+
+    /// Alive cells in the (inclusive) rectangle, anything outside of the board is dead.
+    pub fn count(&self, x_min: i64, x_max: i64, y_min: i64, y_max: i64) -> u32 {
+        let (left, top) = (self.xs.start as i64, self.ys.start as i64);
+        let x0 = (x_min - left).max(0) as usize;
+        let y0 = (y_min - top).max(0) as usize;
+        let x1 = (x_max + 1 - left).min(self.xs.len() as i64);
+        let y1 = (y_max + 1 - top).min(self.ys.len() as i64);
+
+        if x1 <= x0 as i64 || y1 <= y0 as i64 {
+            return 0;
+        }
+        let (x1, y1) = (x1 as usize, y1 as usize);
+
+        let stride = self.ys.len() + 1;
+        self.sums[x1 * stride + y1] + self.sums[x0 * stride + y0]
+            - self.sums[x0 * stride + y1]
+            - self.sums[x1 * stride + y0]
+    }
+}
+
+/// The number of alive neighbours of every cell, indexed by `x * num_cells_y + y`.
+/// With an active region, only the cells in its active tiles are counted (the rest are 0).
+pub fn count_alive_neighbours(
+    cells: &Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    neighbourhood: &Neighbourhood,
+    include_centre: bool,
+    active: Option<&ActiveRegion>,
+) -> Vec<u32> {
+    let mut counts = vec![0; num_cells_x * num_cells_y];
+    let tiles: Vec<(Range<usize>, Range<usize>)> = match active {
+        Some(active) => active.active_tiles().collect(),
+        None => vec![(0..num_cells_x, 0..num_cells_y)],
+    };
+    let cells_in = |(xs, ys): &(Range<usize>, Range<usize>)| {
+        let ys = ys.clone();
+        xs.clone()
+            .flat_map(move |x| ys.clone().map(move |y| (x, y)))
+    };
+
+    // The neighbours of triangles depend on which way they point,
+    // so there are no boxes to count, but there are only 12 of them anyway
+    if neighbourhood.shape == Shape::Triangular {
+        for (x, y) in tiles.iter().flat_map(cells_in) {
+            let mut count = neighbourhood
+                .offsets_at(x, y)
+                .iter()
+                .filter(|(dx, dy)| {
+                    let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                    nx >= 0
+                        && ny >= 0
+                        && (nx as usize) < num_cells_x
+                        && (ny as usize) < num_cells_y
+                        && cells.rows[nx as usize].values[ny as usize].is_alive()
+                })
+                .count() as u32;
+
+            if include_centre && cells.rows[x].values[y].is_alive() {
+                count += 1;
+            }
+            counts[x * num_cells_y + y] = count;
+        }
+
+        return counts;
+    }
+
+    // Only the cells the counted ones can see need summing
+    let reach = neighbourhood.reach();
+    let xs = tiles
+        .iter()
+        .map(|(xs, _)| xs.start.saturating_sub(reach))
+        .min();
+    let ys = tiles
+        .iter()
+        .map(|(_, ys)| ys.start.saturating_sub(reach))
+        .min();
+    let (left, top) = match (xs, ys) {
+        (Some(left), Some(top)) => (left, top),
+        _ => return counts,
+    };
+    let right = tiles
+        .iter()
+        .map(|(xs, _)| xs.end + reach)
+        .max()
+        .unwrap_or(0);
+    let bottom = tiles
+        .iter()
+        .map(|(_, ys)| ys.end + reach)
+        .max()
+        .unwrap_or(0);
+    let area = SummedArea::within(
+        cells,
+        left..right.min(num_cells_x),
+        top..bottom.min(num_cells_y),
+    );
+    let boxes = neighbourhood.boxes();
+
+    for (x, y) in tiles.iter().flat_map(cells_in) {
+        let (xi, yi) = (x as i64, y as i64);
+
+        let mut count: u32 = boxes
+            .iter()
+            .map(|b| {
+                area.count(
+                    xi + b.dx_min as i64,
+                    xi + b.dx_max as i64,
+                    yi + b.dy_min as i64,
+                    yi + b.dy_max as i64,
+                )
+            })
+            .sum();
+
+        // The boxes always cover the cell itself
+        if !include_centre && cells.rows[x].values[y].is_alive() {
+            count -= 1;
+        }
+
+        counts[x * num_cells_y + y] = count;
+    }
+
+    counts
+}
@@ -0,0 +1,363 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use nannou::prelude::*;
+use rand::Rng;
+
+use crate::core::{self, Cell, DEAD_CELL};
+use crate::error::Error;
+use crate::hensel;
+use crate::neighbourhood::{Neighbourhood, Shape};
+use crate::rle::Pattern;
+use crate::rules::{self, Rule};
+use crate::stats::GenerationStats;
+
+// The board without edges: only the cells that aren't dead are stored, so it
+// grows as far as the pattern does (eg. the gliders of a Gosper gun)
+
+// How close (in pixels) the camera can zoom in and out
+pub const MIN_CELL_SIZE: f32 = 0.25;
+pub const MAX_CELL_SIZE: f32 = 64.0;
+
+// Data structures
+// ----------------------------------------------------------------------------
+pub type Point = (i32, i32);
+
+#[derive(Debug, Clone, Default)]
+pub struct Plane {
+    // Every cell that isn't dead
+    pub cells: HashMap<Point, Cell>,
+}
+
+/// Which part of the plane the window shows.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera {
+    // The cell (fractions included) in the middle of the window
+    pub x: f32,
+    pub y: f32,
+    // Pixels across a cell
+    pub cell_size: f32,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+
+/// Rules where dead cells with no neighbours come alive would fill the whole plane.
+pub fn check_rule(rule: &Rule) -> Result<(), Error> {
+    if rule.elementary.is_some() {
+        return Err(Error::Invalid(format!(
+            "{rule} is an elementary rule, which only runs on the fixed size board"
+        )));
+    }
+
+    let born_from_nothing = match (&rule.table, &rule.isotropic) {
+        (Some(table), _) => {
+            let neighbours = vec![rules::DEAD; table.neighbourhood.offsets().len()];
+            table.next_state(rules::DEAD, &neighbours) != rules::DEAD
+        }
+        (None, Some(isotropic)) => isotropic.birth[0],
+        (None, None) => rule.birth[0],
+    };
+    if born_from_nothing {
+        return Err(Error::Invalid(format!(
+            "In {rule} cells are born with no neighbours, which would fill the infinite plane"
+        )));
+    }
+
+    Ok(())
+}
+
+// The cells which count the given one as a neighbour
+fn counted_by(neighbourhood: &Neighbourhood, x: i32, y: i32) -> Vec<Point> {
+    match neighbourhood.shape {
+        // Triangles touching each other are neighbours both ways, but which
+        // ones those are depends on which way the triangle is pointing
+        Shape::Triangular => neighbourhood
+            .offsets_at(x.rem_euclid(2) as usize, y.rem_euclid(2) as usize)
+            .into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .collect(),
+        _ => neighbourhood
+            .offsets()
+            .into_iter()
+            .map(|(dx, dy)| (x - dx, y - dy))
+            .collect(),
+    }
+}
+
+impl Plane {
+    pub fn new() -> Self {
+        Plane::default()
+    }
+
+    /// A random soup of the given size, around 0,0.
+    pub fn random(width: usize, height: usize) -> Self {
+        let mut generator = rand::thread_rng();
+        let mut plane = Plane::new();
+        let (left, top) = (-(width as i32) / 2, -(height as i32) / 2);
+
+        for x in 0..width as i32 {
+            for y in 0..height as i32 {
+                if generator.gen_bool(0.5) {
+                    plane.cells.insert((left + x, top + y), core::ALIVE_CELL);
+                }
+            }
+        }
+
+        plane
+    }
+
+    /// The pattern where its file says it is, or else around 0,0 (like Golly does).
+    pub fn from_pattern(pattern: &Pattern) -> Self {
+        let (left, top) = pattern
+            .position
+            .unwrap_or((-(pattern.width as i32) / 2, -(pattern.height as i32) / 2));
+
+        let mut plane = Plane::new();
+        for (x, y, state) in pattern.cells.iter() {
+            plane.set(left + x, top + y, *state);
+        }
+        plane
+    }
+
+    /// Every cell that isn't dead, with where the top-left corner is.
+    pub fn to_pattern(&self, rule: &Rule) -> Pattern {
+        let (min_x, min_y, max_x, max_y) = self.bounds().unwrap_or((0, 0, -1, -1));
+
+        let mut cells: Vec<(i32, i32, u8)> = self
+            .cells
+            .iter()
+            .map(|((x, y), cell)| (x - min_x, y - min_y, cell.state))
+            .collect();
+        cells.sort_by_key(|(x, y, _state)| (*y, *x));
+
+        Pattern {
+            width: (max_x - min_x + 1) as usize,
+            height: (max_y - min_y + 1) as usize,
+            rule: Some(rule.clone()),
+            cells,
+            position: Some((min_x, min_y)),
+        }
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Cell {
+        self.cells.get(&(x, y)).copied().unwrap_or(DEAD_CELL)
+    }
+
+    /// Change the state of a cell, like drawing on it does.
+    pub fn set(&mut self, x: i32, y: i32, state: u8) {
+        if state == rules::DEAD {
+            self.cells.remove(&(x, y));
+        } else if self.get(x, y).state != state {
+            self.cells.insert((x, y), core::fresh_cell(state));
+        }
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.values().filter(|c| c.is_alive()).count()
+    }
+
+    /// The smallest rectangle with every cell that isn't dead, as (min_x, min_y, max_x, max_y).
+    pub fn bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        let mut points = self.cells.keys();
+        let (x, y) = points.next()?;
+
+        Some(
+            points.fold((*x, *y, *x, *y), |(min_x, min_y, max_x, max_y), (x, y)| {
+                (min_x.min(*x), min_y.min(*y), max_x.max(*x), max_y.max(*y))
+            }),
+        )
+    }
+
+    /// The same for the same cells, wherever they are in the hash map.
+    pub fn hash(&self) -> u64 {
+        let mut cells: Vec<(Point, u8)> = self.cells.iter().map(|(p, c)| (*p, c.state)).collect();
+        cells.sort_unstable();
+
+        let mut hasher = DefaultHasher::new();
+        cells.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The statistics of the plane. There are no usize coordinates to put the
+    /// bounding box in, so the density is the one within the bounding box.
+    pub fn stats(&self, generation: i64, previous: Option<&Plane>) -> GenerationStats {
+        let population = self.population();
+        let (mut births, mut deaths) = (0, 0);
+
+        if let Some(previous) = previous {
+            births = self
+                .cells
+                .iter()
+                .filter(|(p, c)| c.is_alive() && !previous.get(p.0, p.1).is_alive())
+                .count();
+            deaths = previous
+                .cells
+                .iter()
+                .filter(|(p, c)| c.is_alive() && !self.get(p.0, p.1).is_alive())
+                .count();
+        }
+
+        let density = match self.bounds() {
+            Some((min_x, min_y, max_x, max_y)) => {
+                let area = (max_x - min_x + 1) as f32 * (max_y - min_y + 1) as f32;
+                population as f32 / area
+            }
+            None => 0.0,
+        };
+
+        GenerationStats {
+            generation,
+            population,
+            births,
+            deaths,
+            bounding_box: None,
+            density,
+        }
+    }
+
+    // The next state of a cell, looking its neighbours up one by one
+    // (for the rules that don't just count the alive ones)
+    fn next_state_at(&self, rule: &Rule, x: i32, y: i32, known: &mut HashMap<Vec<u8>, u8>) -> u8 {
+        let state = self.get(x, y).state;
+
+        if let Some(table) = &rule.table {
+            let mut states = vec![state];
+            states.extend(
+                table
+                    .neighbourhood
+                    .offsets()
+                    .iter()
+                    .map(|(dx, dy)| self.get(x + dx, y + dy).state),
+            );
+            if let Some(next) = known.get(&states) {
+                return *next;
+            }
+            let next = table.next_state(state, &states[1..]);
+            known.insert(states, next);
+            return next;
+        }
+
+        let mut configuration = 0;
+        for (bit, (dx, dy)) in hensel::NEIGHBOUR_OFFSETS.iter().enumerate() {
+            if self.get(x + dx, y + dy).is_alive() {
+                configuration |= 1 << bit;
+            }
+        }
+        rule.next_state_isotropic(state, configuration)
+    }
+
+    /// One generation later. See `check_rule()` for the rules this works with.
+    pub fn step(&self, rule: &Rule) -> Plane {
+        let mut next_states: HashMap<Point, u8> = HashMap::new();
+
+        if rule.table.is_some() || rule.isotropic.is_some() {
+            // Anything within reach of a cell that isn't dead can change
+            let reach = rule.neighbourhood.reach() as i32;
+            let mut candidates = HashSet::new();
+            for (x, y) in self.cells.keys() {
+                for dx in -reach..=reach {
+                    for dy in -reach..=reach {
+                        candidates.insert((x + dx, y + dy));
+                    }
+                }
+            }
+
+            let mut known = HashMap::new();
+            for (x, y) in candidates {
+                next_states.insert((x, y), self.next_state_at(rule, x, y, &mut known));
+            }
+        } else {
+            // Every alive cell adds one to the count of the cells around it
+            let mut counts: HashMap<Point, usize> = HashMap::new();
+            for ((x, y), cell) in self.cells.iter() {
+                if !cell.is_alive() {
+                    continue;
+                }
+                for point in counted_by(&rule.neighbourhood, *x, *y) {
+                    *counts.entry(point).or_insert(0) += 1;
+                }
+                if rule.include_centre {
+                    *counts.entry((*x, *y)).or_insert(0) += 1;
+                }
+            }
+
+            // Cells with no alive neighbours can still survive (or keep dying)
+            for point in self.cells.keys() {
+                counts.entry(*point).or_insert(0);
+            }
+
+            for ((x, y), count) in counts {
+                let state = rule.next_state(self.get(x, y).state, count);
+                next_states.insert((x, y), state);
+            }
+        }
+
+        let mut next = Plane::new();
+        for ((x, y), state) in next_states {
+            if state != rules::DEAD {
+                next.cells
+                    .insert((x, y), core::next_cell(self.get(x, y), state));
+            }
+        }
+        next
+    }
+}
+
+impl Camera {
+    pub fn new(cell_size: f32) -> Self {
+        Camera {
+            x: 0.0,
+            y: 0.0,
+            cell_size,
+        }
+    }
+
+    /// Where the top-left corner of a cell is in the window.
+    pub fn to_screen(&self, x: i32, y: i32) -> Point2 {
+        pt2(
+            (x as f32 - self.x) * self.cell_size,
+            -(y as f32 - self.y) * self.cell_size,
+        )
+    }
+
+    /// The cell under a point of the window.
+    pub fn to_cell(&self, point: Point2) -> Point {
+        (
+            (point.x / self.cell_size + self.x).floor() as i32,
+            (-point.y / self.cell_size + self.y).floor() as i32,
+        )
+    }
+
+    /// The cells in view, as (min_x, min_y, max_x, max_y).
+    pub fn visible(&self, app_width: f32, app_height: f32) -> (i32, i32, i32, i32) {
+        let (min_x, min_y) = self.to_cell(pt2(-app_width * 0.5, app_height * 0.5));
+        let (max_x, max_y) = self.to_cell(pt2(app_width * 0.5, -app_height * 0.5));
+        (min_x, min_y, max_x, max_y)
+    }
+
+    /// Move by a fraction of the window (eg. -0.25, 0.0 is a quarter of it to the left).
+    pub fn pan(&mut self, dx: f32, dy: f32, app_width: f32, app_height: f32) {
+        self.x += dx * app_width / self.cell_size;
+        self.y += dy * app_height / self.cell_size;
+    }
+
+    /// Zoom in (factor above 1) or out, keeping the middle of the window where it is.
+    pub fn zoom(&mut self, factor: f32) {
+        self.cell_size = (self.cell_size * factor).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+    }
+
+    /// Zoom and move so that the given cells fill the window.
+    pub fn fit(&mut self, bounds: (i32, i32, i32, i32), app_width: f32, app_height: f32) {
+        let (min_x, min_y, max_x, max_y) = bounds;
+        let (width, height) = ((max_x - min_x + 1) as f32, (max_y - min_y + 1) as f32);
+
+        self.x = min_x as f32 + width * 0.5;
+        self.y = min_y as f32 + height * 0.5;
+        // A bit of margin around the pattern
+        self.cell_size = (app_width / (width + 4.0))
+            .min(app_height / (height + 4.0))
+            .clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+    }
+}
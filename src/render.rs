@@ -0,0 +1,176 @@
+use nannou::geom::Tri;
+use nannou::prelude::*;
+
+use crate::core::{self, Cells, RenderMode};
+use crate::geometry::Layout;
+use crate::logging;
+use crate::plane::{Camera, Plane};
+use crate::rules::Rule;
+use crate::theme::Theme;
+
+// Drawing every cell with its own quad means a draw command per cell, dead
+// ones included, which gets slow quickly on big boards. Instead, the cells
+// that don't look like the background go in a single mesh of triangles,
+// which the GPU draws in one go.
+//
+// The same triangles can be filled in on the CPU with `rasterise()`, to
+// check what ends up on screen without a window (or a GPU).
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Triangle {
+    pub points: [Point2; 3],
+    pub color: Rgb8,
+}
+
+/// The pixels of a frame drawn off-screen, row after row from the top-left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Rgb8>,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+
+// A convex polygon as a fan of triangles around its first corner
+fn push_polygon(triangles: &mut Vec<Triangle>, points: &[Point2], color: Rgb8) {
+    for i in 1..points.len().saturating_sub(1) {
+        triangles.push(Triangle {
+            points: [points[0], points[i], points[i + 1]],
+            color,
+        });
+    }
+}
+
+/// The triangles of every cell on the board that isn't the colour of the background.
+pub fn board_mesh(
+    cells: &Cells,
+    layout: &Layout,
+    mode: RenderMode,
+    theme: &Theme,
+    rule: &Rule,
+) -> Vec<Triangle> {
+    let mut triangles = Vec::new();
+
+    for (x, row) in cells.rows[..layout.num_cells_x].iter().enumerate() {
+        for (y, cell) in row.values[..layout.num_cells_y].iter().enumerate() {
+            let color = core::cell_color(cell, mode, theme, rule);
+            if color != theme.background {
+                push_polygon(&mut triangles, &layout.polygon(x, y), color);
+            }
+        }
+    }
+
+    triangles
+}
+
+/// The triangles of the cells of the plane in view of the camera.
+pub fn plane_mesh(
+    plane: &Plane,
+    camera: &Camera,
+    app_width: f32,
+    app_height: f32,
+    mode: RenderMode,
+    theme: &Theme,
+    rule: &Rule,
+) -> Vec<Triangle> {
+    let size = camera.cell_size;
+    let (min_x, min_y, max_x, max_y) = camera.visible(app_width, app_height);
+    let mut triangles = Vec::new();
+
+    for ((x, y), cell) in plane.cells.iter() {
+        if *x < min_x || *x > max_x || *y < min_y || *y > max_y {
+            continue;
+        }
+
+        let color = core::cell_color(cell, mode, theme, rule);
+        if color == theme.background {
+            continue;
+        }
+
+        let corner = camera.to_screen(*x, *y);
+        let square = [
+            corner,
+            pt2(corner.x, corner.y - size),
+            pt2(corner.x + size, corner.y - size),
+            pt2(corner.x + size, corner.y),
+        ];
+        push_polygon(&mut triangles, &square, color);
+    }
+
+    triangles
+}
+
+/// Draw all the triangles as one mesh.
+pub fn draw_mesh(triangles: &[Triangle], canvas: &Draw) {
+    log::trace!(target: logging::RENDER, "Drawing {} triangles", triangles.len());
+    if triangles.is_empty() {
+        return;
+    }
+
+    canvas.mesh().tris_colored(triangles.iter().map(|triangle| {
+        let [a, b, c] = triangle.points;
+        let color = triangle.color;
+        Tri([
+            (a.extend(0.0), color),
+            (b.extend(0.0), color),
+            (c.extend(0.0), color),
+        ])
+    }));
+}
+
+// Twice the signed area of a, b, p: positive when p is on the left of a -> b
+fn edge(a: Point2, b: Point2, p: Point2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Fill the triangles in on the CPU, in the window coordinates nannou uses
+/// (0,0 in the middle, y going up). A pixel takes the colour of the last
+/// triangle covering its centre.
+pub fn rasterise(triangles: &[Triangle], background: Rgb8, width: usize, height: usize) -> Image {
+    let mut image = Image {
+        width,
+        height,
+        pixels: vec![background; width * height],
+    };
+    let (half_width, half_height) = (width as f32 * 0.5, height as f32 * 0.5);
+
+    for triangle in triangles {
+        let [a, b, c] = triangle.points;
+        if edge(a, b, c) == 0.0 {
+            continue;
+        }
+
+        // Only look at the pixels around the triangle
+        let (min_x, max_x) = (a.x.min(b.x).min(c.x), a.x.max(b.x).max(c.x));
+        let (min_y, max_y) = (a.y.min(b.y).min(c.y), a.y.max(b.y).max(c.y));
+        let left = (min_x + half_width).floor().max(0.0) as usize;
+        let right = ((max_x + half_width).ceil().max(0.0) as usize).min(width);
+        let top = (half_height - max_y).floor().max(0.0) as usize;
+        let bottom = ((half_height - min_y).ceil().max(0.0) as usize).min(height);
+
+        for py in top..bottom {
+            for px in left..right {
+                let centre = pt2(px as f32 + 0.5 - half_width, half_height - py as f32 - 0.5);
+                let (ab, bc, ca) = (edge(a, b, centre), edge(b, c, centre), edge(c, a, centre));
+                let inside =
+                    (ab >= 0.0 && bc >= 0.0 && ca >= 0.0) || (ab <= 0.0 && bc <= 0.0 && ca <= 0.0);
+                if inside {
+                    image.pixels[py * width + px] = triangle.color;
+                }
+            }
+        }
+    }
+
+    image
+}
+
+impl Image {
+    /// The colour of a pixel, counting from the top-left corner.
+    pub fn pixel(&self, x: usize, y: usize) -> Rgb8 {
+        self.pixels[y * self.width + x]
+    }
+}
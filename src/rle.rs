@@ -0,0 +1,354 @@
+use std::fs;
+use std::path::Path;
+
+use crate::core::{self, Cells};
+use crate::error::Error;
+use crate::rules::{self, Rule};
+
+// Golly wraps the lines of the RLE files it writes at 70 characters
+pub const MAX_LINE_LENGTH: usize = 70;
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    pub rule: Option<Rule>,
+    // x, y and state of every cell that isn't dead, from the top-left corner
+    pub cells: Vec<(i32, i32, u8)>,
+    // Where the top-left corner is on the infinite plane, if the file says
+    // (Golly's "#CXRLE Pos=x,y" line), otherwise the pattern goes in the middle
+    pub position: Option<(i32, i32)>,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+
+/// The letter(s) for a state in multi-state RLE: '.' is dead, 'A' to 'X'
+/// are the states 1 to 24, then 'pA' to 'pX' are 25 to 48, and so on.
+pub fn state_to_letters(state: u8) -> String {
+    if state == rules::DEAD {
+        return String::from(".");
+    }
+
+    let index = state - 1;
+    let letter = (b'A' + index % 24) as char;
+
+    match index / 24 {
+        0 => letter.to_string(),
+        prefix => format!("{}{letter}", (b'p' + prefix - 1) as char),
+    }
+}
+
+// "#CXRLE Pos=-10,-5 Gen=0", only the position matters here
+fn parse_position(line: &str) -> Result<Option<(i32, i32)>, String> {
+    let position = match line.split_whitespace().find_map(|w| w.strip_prefix("Pos=")) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    let invalid = || format!("Invalid position '{position}' in RLE file");
+    let (x, y) = position.split_once(',').ok_or_else(invalid)?;
+    Ok(Some((
+        x.trim().parse().map_err(|_| invalid())?,
+        y.trim().parse().map_err(|_| invalid())?,
+    )))
+}
+
+fn parse_header(line: &str) -> Result<(usize, usize, Option<Rule>), String> {
+    let mut width = None;
+    let mut height = None;
+    let mut rule = None;
+
+    // The rule is always last, and can have commas of its own (R5,C0,M1,..)
+    let (line, rule_text) = match line.find("rule") {
+        Some(start) => (&line[..start], Some(&line[start..])),
+        None => (line, None),
+    };
+    if let Some(rule_text) = rule_text {
+        let (_key, value) = rule_text
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid RLE header '{line}'"))?;
+        rule = Some(Rule::parse(value).map_err(|e| e.to_string())?);
+    }
+
+    for item in line.split(',').filter(|i| !i.trim().is_empty()) {
+        let (key, value) = item
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid RLE header '{line}'"))?;
+        let value = value.trim();
+
+        match key.trim() {
+            "x" => width = value.parse().ok(),
+            "y" => height = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(format!(
+            "Invalid RLE header '{line}', expected x = .., y = .."
+        )),
+    }
+}
+
+pub fn parse_rle(text: &str) -> Result<Pattern, Error> {
+    let mut position = None;
+    for (number, line) in text.lines().enumerate() {
+        if let Some(extension) = line.trim().strip_prefix("#CXRLE") {
+            position = parse_position(extension).map_err(|e| Error::parse(number + 1, None, e))?;
+        }
+    }
+
+    // Numbered from 1, for the errors
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number + 1, line))
+        .filter(|(_, l)| !l.trim().is_empty() && !l.trim().starts_with('#'));
+
+    let (number, header) = lines.next().ok_or("Empty RLE file")?;
+    let (width, height, rule) =
+        parse_header(header.trim()).map_err(|e| Error::parse(number, None, e))?;
+
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    let mut count = String::new();
+    let mut prefix: Option<u8> = None;
+
+    'body: for (number, line) in lines {
+        for (column, c) in line.chars().enumerate() {
+            let error = |message: String| Error::parse(number, Some(column + 1), message);
+            if c.is_whitespace() {
+                continue;
+            }
+
+            let run = if count.is_empty() {
+                1
+            } else {
+                count
+                    .parse::<i32>()
+                    .map_err(|_| error(format!("Invalid run length '{count}'")))?
+            };
+
+            let state = match c {
+                '0'..='9' => {
+                    count.push(c);
+                    continue;
+                }
+                'p'..='y' => {
+                    prefix = Some(c as u8 - b'p' + 1);
+                    continue;
+                }
+                '!' => break 'body,
+                '$' => {
+                    y += run;
+                    x = 0;
+                    count.clear();
+                    continue;
+                }
+                'b' | '.' => rules::DEAD,
+                'o' => rules::ALIVE,
+                'A'..='X' => {
+                    let state = prefix.unwrap_or(0) as u32 * 24 + (c as u8 - b'A') as u32 + 1;
+                    u8::try_from(state).map_err(|_| error(format!("State {state} is too large")))?
+                }
+                _ => return Err(error(format!("Unexpected '{c}' in RLE pattern"))),
+            };
+
+            if state != rules::DEAD {
+                for i in 0..run {
+                    cells.push((x + i, y, state));
+                }
+            }
+
+            x += run;
+            count.clear();
+            prefix = None;
+        }
+    }
+
+    Ok(Pattern {
+        width,
+        height,
+        rule,
+        cells,
+        position,
+    })
+}
+
+pub fn load_rle<P: AsRef<Path>>(path: P) -> Result<Pattern, Error> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+
+    parse_rle(&text).map_err(|e| e.in_file(path))
+}
+
+/// The pattern as RLE. Fails for rules that can't be written down, as
+/// the file would be read back with some other rule.
+pub fn write_rle(pattern: &Pattern) -> Result<String, Error> {
+    // Plain b/o for 2 state patterns, so that every program can read them
+    let multi_state = pattern.cells.iter().any(|c| c.2 > rules::ALIVE)
+        || pattern.rule.as_ref().map(|r| r.states > 2).unwrap_or(false);
+
+    let letters = |state: u8| {
+        if multi_state {
+            state_to_letters(state)
+        } else if state == rules::DEAD {
+            String::from("b")
+        } else {
+            String::from("o")
+        }
+    };
+
+    let mut header = format!("x = {}, y = {}", pattern.width, pattern.height);
+    if let Some(rule) = &pattern.rule {
+        header.push_str(&format!(", rule = {}", rule.to_rulestring()?));
+    }
+
+    // Same as Golly, the position goes before the header
+    if let Some((x, y)) = pattern.position {
+        header = format!("#CXRLE Pos={x},{y}\n{header}");
+    }
+
+    let min_x = pattern.cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let min_y = pattern.cells.iter().map(|c| c.1).min().unwrap_or(0);
+
+    let mut sorted = pattern.cells.clone();
+    sorted.sort_by_key(|(x, y, _state)| (*y, *x));
+
+    // Runs of (count, letters), trailing dead cells in a row are left out
+    let mut runs: Vec<(i32, String)> = Vec::new();
+    let mut push = |count: i32, letters: String| {
+        if count == 0 {
+            return;
+        }
+        match runs.last_mut() {
+            Some(last) if last.1 == letters => last.0 += count,
+            _ => runs.push((count, letters)),
+        }
+    };
+
+    let (mut x, mut y) = (min_x, min_y);
+    for (cx, cy, state) in sorted {
+        if cy > y {
+            push(cy - y, String::from("$"));
+            y = cy;
+            x = min_x;
+        }
+        push(cx - x, letters(rules::DEAD));
+        push(1, letters(state));
+        x = cx + 1;
+    }
+    push(1, String::from("!"));
+
+    let mut body = String::new();
+    let mut line_length = 0;
+    for (count, letters) in runs {
+        let item = if count > 1 {
+            format!("{count}{letters}")
+        } else {
+            letters
+        };
+
+        if line_length + item.len() > MAX_LINE_LENGTH {
+            body.push('\n');
+            line_length = 0;
+        }
+        line_length += item.len();
+        body.push_str(&item);
+    }
+
+    Ok(format!("{header}\n{body}\n"))
+}
+
+pub fn save_rle<P: AsRef<Path>>(pattern: &Pattern, path: P) -> Result<(), Error> {
+    let path = path.as_ref();
+    let text = write_rle(pattern)?;
+    fs::write(path, text).map_err(|e| Error::io(path, e))
+}
+
+/// The smallest pattern containing every cell on the board that isn't dead.
+pub fn pattern_from_cells(
+    cells: &Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    rule: &Rule,
+) -> Pattern {
+    let mut points = Vec::new();
+
+    for (x, row) in cells.rows.iter().enumerate().take(num_cells_x) {
+        for (y, cell) in row.values.iter().enumerate().take(num_cells_y) {
+            if cell.state != rules::DEAD {
+                points.push((x as i32, y as i32, cell.state));
+            }
+        }
+    }
+
+    let min_x = points.iter().map(|p| p.0).min().unwrap_or(0);
+    let min_y = points.iter().map(|p| p.1).min().unwrap_or(0);
+    let max_x = points.iter().map(|p| p.0).max().unwrap_or(-1);
+    let max_y = points.iter().map(|p| p.1).max().unwrap_or(-1);
+
+    Pattern {
+        width: (max_x - min_x + 1) as usize,
+        height: (max_y - min_y + 1) as usize,
+        rule: Some(rule.clone()),
+        cells: points
+            .into_iter()
+            .map(|(x, y, state)| (x - min_x, y - min_y, state))
+            .collect(),
+        position: None,
+    }
+}
+
+/// Put the pattern on the board, with its top-left corner at the given cell.
+/// Returns how many cells fell outside of the board.
+pub fn place_pattern(
+    pattern: &Pattern,
+    cells: &mut Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+    offset_x: i32,
+    offset_y: i32,
+) -> usize {
+    let mut clipped = 0;
+
+    for (x, y, state) in pattern.cells.iter() {
+        let (x, y) = (x + offset_x, y + offset_y);
+        if x < 0 || y < 0 || x as usize >= num_cells_x || y as usize >= num_cells_y {
+            clipped += 1;
+            continue;
+        }
+
+        cells.rows[x as usize].values[y as usize] = core::fresh_cell(*state);
+    }
+
+    clipped
+}
+
+/// Put the pattern in the middle of an empty board.
+pub fn centred_pattern(
+    pattern: &Pattern,
+    num_cells_x: usize,
+    num_cells_y: usize,
+) -> (Cells, usize) {
+    let mut cells = Cells {
+        rows: core::get_all_cells_as_dead(num_cells_x, num_cells_y),
+    };
+
+    let offset_x = (num_cells_x as i32 - pattern.width as i32) / 2;
+    let offset_y = (num_cells_y as i32 - pattern.height as i32) / 2;
+    let clipped = place_pattern(
+        pattern,
+        &mut cells,
+        num_cells_x,
+        num_cells_y,
+        offset_x,
+        offset_y,
+    );
+
+    (cells, clipped)
+}
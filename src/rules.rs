@@ -0,0 +1,652 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::elementary::Elementary;
+use crate::error::Error;
+use crate::hensel::{self, Isotropic};
+use crate::neighbourhood::{Neighbourhood, Shape};
+use crate::ruletable::{self, RuleTable, TableNeighbourhood};
+
+// Cell states, for rules with more than 2 of them:
+// 0 is dead, 1 is alive, and everything above is dying
+pub const DEAD: u8 = 0;
+pub const ALIVE: u8 = 1;
+
+// Larger than Life neighbourhoods bigger than this don't fit on the board anyway
+pub const MAX_RADIUS: usize = 32;
+
+// Data structures
+// ----------------------------------------------------------------------------
+
+/// An outer totalistic rule, optionally "Generations"-style: instead of
+/// dying straight away, cells go through `states - 2` refractory states
+/// where they don't count as alive and can't be born again.
+///
+/// The neighbourhood can be bigger than the usual 8 cells, as in
+/// Larger than Life rules such as Bosco's Rule (R5,C0,M1,S34..58,B34..45,NM).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    // Indexed by the number of alive neighbours
+    pub birth: Vec<bool>,
+    pub survival: Vec<bool>,
+    // 2 for the usual Life-like rules
+    pub states: u8,
+    pub neighbourhood: Neighbourhood,
+    // Whether the cell counts as one of its own neighbours (M1 in LtL)
+    pub include_centre: bool,
+    // Set for non-totalistic rules, in which case birth and survival
+    // only hold the counts where every configuration is in
+    pub isotropic: Option<Isotropic>,
+    // Set for rules from a .rule file (or Wireworld), in which case
+    // the table says what happens and birth and survival are empty
+    pub table: Option<Arc<RuleTable>>,
+    // Set for elementary 1D rules (W30, W110..), which step a single row
+    pub elementary: Option<Elementary>,
+}
+
+pub const NAMED_RULES: [(&str, &str); 12] = [
+    ("life", "B3/S23"),
+    ("highlife", "B36/S23"),
+    ("seeds", "B2/S"),
+    ("day-and-night", "B3678/S34678"),
+    ("brians-brain", "B2/S/C3"),
+    ("star-wars", "B2/S345/C4"),
+    ("bosco", "R5,C0,M1,S34..58,B34..45,NM"),
+    ("majority", "R4,C0,M1,S41..81,B41..81,NM"),
+    ("tlife", "B3/S2-i34q"),
+    ("hex-life", "B2/S34H"),
+    ("rule-30", "W30"),
+    ("rule-110", "W110"),
+];
+
+// Functions
+// ----------------------------------------------------------------------------
+// One digit per count, or comma separated numbers when there can be 10 or more
+// neighbours (eg. "3,4,5" for triangles)
+fn parse_counts(digits: &str, max: usize, rulestring: &str) -> Result<Vec<bool>, String> {
+    let mut counts = vec![false; max + 1];
+
+    let numbers: Vec<String> = if max > 9 {
+        digits
+            .split(',')
+            .filter(|n| !n.is_empty())
+            .map(String::from)
+            .collect()
+    } else {
+        digits.chars().map(String::from).collect()
+    };
+
+    for number in numbers {
+        match number.parse::<usize>() {
+            Ok(n) if n <= max => counts[n] = true,
+            _ => {
+                return Err(format!(
+                "Invalid rule '{rulestring}': '{number}' is not a number of neighbours (0-{max})"
+            ))
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+fn parse_states(digits: &str, rulestring: &str) -> Result<u8, String> {
+    match digits.parse::<u8>() {
+        Ok(states) if states >= 2 => Ok(states),
+        _ => Err(format!(
+            "Invalid rule '{rulestring}': the number of states must be between 2 and 255"
+        )),
+    }
+}
+
+// "34..58" (or just "34") in a Larger than Life rule, or more of them
+// for counts with gaps in between ("2..3,5")
+fn parse_ranges(ranges: &[&str], max: usize, rulestring: &str) -> Result<Vec<bool>, String> {
+    let mut counts = vec![false; max + 1];
+
+    for range in ranges.iter().filter(|r| !r.is_empty()) {
+        let range = parse_range(range, max, rulestring)?;
+        counts.iter_mut().zip(range).for_each(|(c, r)| *c |= r);
+    }
+
+    Ok(counts)
+}
+
+fn parse_range(range: &str, max: usize, rulestring: &str) -> Result<Vec<bool>, String> {
+    let mut counts = vec![false; max + 1];
+
+    let (min_text, max_text) = range.split_once("..").unwrap_or((range, range));
+    let invalid = || format!("Invalid rule '{rulestring}': bad range '{range}'");
+    let low: usize = min_text.parse().map_err(|_| invalid())?;
+    let high: usize = max_text.parse().map_err(|_| invalid())?;
+
+    if low > high || high > max {
+        return Err(format!(
+            "Invalid rule '{rulestring}': the range '{range}' must be within 0..{max}"
+        ));
+    }
+
+    counts[low..=high].iter_mut().for_each(|c| *c = true);
+    Ok(counts)
+}
+
+fn format_counts(counts: &[bool]) -> String {
+    let numbers: Vec<String> = (0..counts.len())
+        .filter(|n| counts[*n])
+        .map(|n| n.to_string())
+        .collect();
+
+    if counts.len() > 10 {
+        numbers.join(",")
+    } else {
+        numbers.concat()
+    }
+}
+
+// Every run of counts in a row as "low..high" (or just "low"), eg. "2..3,5"
+fn format_ranges(counts: &[bool]) -> String {
+    let mut ranges = Vec::new();
+    let mut n = 0;
+
+    while n < counts.len() {
+        if !counts[n] {
+            n += 1;
+            continue;
+        }
+
+        let low = n;
+        while n + 1 < counts.len() && counts[n + 1] {
+            n += 1;
+        }
+        ranges.push(if low == n {
+            low.to_string()
+        } else {
+            format!("{low}..{n}")
+        });
+        n += 1;
+    }
+
+    ranges.join(",")
+}
+
+impl Rule {
+    pub fn conway() -> Self {
+        let mut rule = Rule::empty(Neighbourhood::moore(1), false);
+        rule.birth[3] = true;
+        rule.survival[2] = true;
+        rule.survival[3] = true;
+        rule
+    }
+
+    // Nothing is born and nothing survives
+    fn empty(neighbourhood: Neighbourhood, include_centre: bool) -> Self {
+        let max = neighbourhood.size() + include_centre as usize;
+
+        Rule {
+            birth: vec![false; max + 1],
+            survival: vec![false; max + 1],
+            states: 2,
+            neighbourhood,
+            include_centre,
+            isotropic: None,
+            table: None,
+            elementary: None,
+        }
+    }
+
+    /// A rule from a .rule file.
+    pub fn from_table(table: RuleTable) -> Self {
+        let neighbourhood = match table.neighbourhood {
+            TableNeighbourhood::Moore => Neighbourhood::moore(1),
+            TableNeighbourhood::VonNeumann => Neighbourhood::von_neumann(1),
+            TableNeighbourhood::Hexagonal => Neighbourhood::hexagonal(1),
+            TableNeighbourhood::OneDimensional => Neighbourhood {
+                shape: Shape::Custom(vec![(-1, 0), (1, 0)]),
+                radius: 1,
+            },
+        };
+
+        let mut rule = Rule::empty(neighbourhood, false);
+        rule.states = table.states;
+        rule.table = Some(Arc::new(table));
+        rule
+    }
+
+    /// An elementary 1D rule, numbered from 0 to 255 like Wolfram does.
+    pub fn elementary(number: u8) -> Self {
+        let neighbourhood = Neighbourhood {
+            shape: Shape::Custom(vec![(-1, 0), (1, 0)]),
+            radius: 1,
+        };
+
+        let mut rule = Rule::empty(neighbourhood, false);
+        rule.elementary = Some(Elementary::new(number));
+        rule
+    }
+
+    /// Whether this is a rule on the classic 8 cells neighbourhood.
+    pub fn is_classic(&self) -> bool {
+        self.neighbourhood.is_classic() && !self.include_centre
+    }
+
+    /// The most alive neighbours a cell can have.
+    pub fn max_count(&self) -> usize {
+        self.neighbourhood.size() + self.include_centre as usize
+    }
+
+    /// Use another neighbourhood, keeping the birth and survival counts
+    /// (non-totalistic rules fall back to the counts where every configuration is in).
+    /// Rules from a .rule file and elementary rules come with their own neighbourhood,
+    /// and are left as they are.
+    pub fn with_neighbourhood(&self, neighbourhood: Neighbourhood) -> Self {
+        if self.table.is_some() || self.elementary.is_some() {
+            return self.clone();
+        }
+
+        let mut rule = self.clone();
+        rule.neighbourhood = neighbourhood;
+        rule.isotropic = None;
+
+        let max = rule.max_count();
+        rule.birth.resize(max + 1, false);
+        rule.survival.resize(max + 1, false);
+        rule
+    }
+
+    /// Parse either a name from `NAMED_RULES` or a rulestring, in any of:
+    /// "B3/S23", "B2/S/C3", "23/3" (S/B), "345/2/4" (S/B/C),
+    /// "B2/S34H" (hexagonal), "B2/S013V" (von Neumann), "B4/S3,4,5T" (triangular), isotropic
+    /// non-totalistic "B2n3/S23-q", Larger than Life "R5,C0,M1,S34..58,B34..45,NM"
+    /// or elementary "W110".
+    /// Anything else is looked up as a .rule file, either the path
+    /// given or NAME.rule in the current directory (like Golly does).
+    pub fn parse(rulestring: &str) -> Result<Self, Error> {
+        let rulestring = rulestring.trim();
+
+        if rulestring.eq_ignore_ascii_case("wireworld") {
+            return ruletable::parse_rule_table(ruletable::WIREWORLD).map(Rule::from_table);
+        }
+        if rulestring.ends_with(".rule") {
+            return ruletable::load_rule_table(rulestring).map(Rule::from_table);
+        }
+
+        match Rule::parse_rulestring(rulestring) {
+            Ok(rule) => Ok(rule),
+            Err(e) => {
+                let path = format!("{rulestring}.rule");
+                if Path::new(&path).is_file() {
+                    ruletable::load_rule_table(path).map(Rule::from_table)
+                } else {
+                    Err(Error::Invalid(e))
+                }
+            }
+        }
+    }
+
+    fn parse_rulestring(rulestring: &str) -> Result<Self, String> {
+        if let Some((_name, named)) = NAMED_RULES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(rulestring))
+        {
+            return Rule::parse(named).map_err(|e| e.to_string());
+        }
+
+        if rulestring.starts_with(['R', 'r']) && rulestring.contains(',') {
+            return Rule::parse_larger_than_life(rulestring);
+        }
+
+        // Golly's syntax for Wolfram's elementary rules
+        if let Some(number) = rulestring.strip_prefix(['W', 'w']) {
+            return match number.parse::<u8>() {
+                Ok(number) => Ok(Rule::elementary(number)),
+                Err(_) => Err(format!(
+                    "Invalid rule '{rulestring}': elementary rules go from W0 to W255"
+                )),
+            };
+        }
+
+        // Same suffixes as Golly for the other radius 1 neighbourhoods, plus T for
+        // triangles (only uppercase, 't' is also a Hensel letter)
+        let (body, neighbourhood) = match rulestring.chars().last() {
+            Some('H') | Some('h') => (
+                &rulestring[..rulestring.len() - 1],
+                Neighbourhood::hexagonal(1),
+            ),
+            Some('V') | Some('v') => (
+                &rulestring[..rulestring.len() - 1],
+                Neighbourhood::von_neumann(1),
+            ),
+            Some('T') => (
+                &rulestring[..rulestring.len() - 1],
+                Neighbourhood::triangular(),
+            ),
+            _ => (rulestring, Neighbourhood::moore(1)),
+        };
+        let max = neighbourhood.size();
+
+        let parts: Vec<&str> = body.split('/').collect();
+        let has_letters = parts
+            .iter()
+            .any(|p| p.starts_with(|c: char| c.is_ascii_alphabetic()));
+
+        let mut rule = Rule::empty(neighbourhood, false);
+
+        if has_letters
+            && parts
+                .iter()
+                .any(|p| hensel::is_hensel(p.get(1..).unwrap_or("")))
+        {
+            return Rule::parse_isotropic(&parts, rule.neighbourhood, rulestring);
+        }
+
+        if has_letters {
+            for part in parts {
+                let mut chars = part.chars();
+                let letter = chars.next().map(|c| c.to_ascii_uppercase());
+                let rest = chars.as_str();
+
+                match letter {
+                    Some('B') => rule.birth = parse_counts(rest, max, rulestring)?,
+                    Some('S') => rule.survival = parse_counts(rest, max, rulestring)?,
+                    Some('C') | Some('G') => rule.states = parse_states(rest, rulestring)?,
+                    _ => return Err(format!("Invalid rule '{rulestring}': unexpected '{part}'")),
+                }
+            }
+        } else {
+            match parts.as_slice() {
+                [survival, birth] => {
+                    rule.survival = parse_counts(survival, max, rulestring)?;
+                    rule.birth = parse_counts(birth, max, rulestring)?;
+                }
+                [survival, birth, states] => {
+                    rule.survival = parse_counts(survival, max, rulestring)?;
+                    rule.birth = parse_counts(birth, max, rulestring)?;
+                    rule.states = parse_states(states, rulestring)?;
+                }
+                _ => return Err(format!("Invalid rule '{rulestring}'")),
+            }
+        }
+
+        Ok(rule)
+    }
+
+    // B and S in Hensel notation, eg. B2n3/S23-q
+    fn parse_isotropic(
+        parts: &[&str],
+        neighbourhood: Neighbourhood,
+        rulestring: &str,
+    ) -> Result<Self, String> {
+        if !neighbourhood.is_classic() {
+            return Err(format!(
+                "Invalid rule '{rulestring}': non-totalistic rules only work with the 8 cells around"
+            ));
+        }
+
+        let mut rule = Rule::empty(neighbourhood, false);
+        let mut isotropic = Isotropic {
+            birth: [false; 256],
+            survival: [false; 256],
+        };
+
+        for part in parts {
+            let mut chars = part.chars();
+            let letter = chars.next().map(|c| c.to_ascii_uppercase());
+            let rest = chars.as_str();
+
+            match letter {
+                Some('B') => isotropic.birth = hensel::parse_transitions(rest, rulestring)?,
+                Some('S') => isotropic.survival = hensel::parse_transitions(rest, rulestring)?,
+                Some('C') | Some('G') => rule.states = parse_states(rest, rulestring)?,
+                _ => return Err(format!("Invalid rule '{rulestring}': unexpected '{part}'")),
+            }
+        }
+
+        rule.birth = hensel::totalistic_counts(&isotropic.birth);
+        rule.survival = hensel::totalistic_counts(&isotropic.survival);
+        rule.isotropic = Some(isotropic);
+
+        Ok(rule)
+    }
+
+    // Rr,Cc,Mm,Smin..max,Bmin..max,Nn
+    fn parse_larger_than_life(rulestring: &str) -> Result<Self, String> {
+        let mut radius = None;
+        let mut states = 2;
+        let mut include_centre = false;
+        let mut survival = Vec::new();
+        let mut birth = Vec::new();
+        let mut shape = 'M';
+        // Which of S and B the numbers that don't start with a letter go to
+        let mut last = None;
+
+        for item in rulestring.split(',') {
+            let item = item.trim();
+            let mut chars = item.chars();
+            let letter = chars.next().map(|c| c.to_ascii_uppercase());
+            let value = chars.as_str();
+            let invalid = || format!("Invalid rule '{rulestring}': bad '{item}'");
+
+            match letter {
+                Some('R') => {
+                    let r: usize = value.parse().map_err(|_| invalid())?;
+                    if !(1..=MAX_RADIUS).contains(&r) {
+                        return Err(format!(
+                            "Invalid rule '{rulestring}': the radius must be between 1 and {MAX_RADIUS}"
+                        ));
+                    }
+                    radius = Some(r);
+                }
+                // C0 and C1 are the same as C2 in LtL
+                Some('C') => states = value.parse::<u8>().map_err(|_| invalid())?.max(2),
+                Some('M') => {
+                    include_centre = match value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(invalid()),
+                    }
+                }
+                Some('S') => survival.push(value),
+                Some('B') => birth.push(value),
+                Some('0'..='9') => match last {
+                    Some('S') => survival.push(item),
+                    Some('B') => birth.push(item),
+                    _ => return Err(invalid()),
+                },
+                Some('N') => {
+                    shape = value
+                        .chars()
+                        .next()
+                        .map(|c| c.to_ascii_uppercase())
+                        .ok_or_else(invalid)?
+                }
+                _ => return Err(invalid()),
+            }
+
+            if letter.is_some_and(|l| l.is_ascii_alphabetic()) {
+                last = letter;
+            }
+        }
+
+        let radius = radius.ok_or_else(|| format!("Invalid rule '{rulestring}': missing R"))?;
+        let neighbourhood = match shape {
+            'M' => Neighbourhood::moore(radius),
+            'N' => Neighbourhood::von_neumann(radius),
+            'H' => Neighbourhood::hexagonal(radius),
+            'T' if radius == 1 => Neighbourhood::triangular(),
+            _ => {
+                return Err(format!(
+                "Invalid rule '{rulestring}': the neighbourhood must be NM, NN, NH or NT (R1 only)"
+            ))
+            }
+        };
+
+        let mut rule = Rule::empty(neighbourhood, include_centre);
+        let max = rule.max_count();
+        rule.states = states;
+        rule.survival = parse_ranges(&survival, max, rulestring)?;
+        rule.birth = parse_ranges(&birth, max, rulestring)?;
+
+        Ok(rule)
+    }
+
+    /// The rule written down so that `parse()` reads it back the same, for
+    /// files. Custom neighbourhoods can't be: their mask is in a file of its own.
+    pub fn to_rulestring(&self) -> Result<String, Error> {
+        let custom = matches!(self.neighbourhood.shape, Shape::Custom(_));
+        if custom && self.table.is_none() && self.elementary.is_none() {
+            return Err(Error::Invalid(format!(
+                "The rule {self} can't be written down, only with the file of its neighbourhood"
+            )));
+        }
+
+        Ok(self.to_string())
+    }
+
+    /// The state of a cell in the next generation.
+    pub fn next_state(&self, state: u8, alive_neighbours: usize) -> u8 {
+        let born = self.birth.get(alive_neighbours).copied().unwrap_or(false);
+        let survives = self
+            .survival
+            .get(alive_neighbours)
+            .copied()
+            .unwrap_or(false);
+
+        self.transition(state, born, survives)
+    }
+
+    /// The state of a cell in the next generation, given which of the 8 cells
+    /// around are alive (see `hensel::configuration()`). Only for non-totalistic rules.
+    pub fn next_state_isotropic(&self, state: u8, configuration: u8) -> u8 {
+        match &self.isotropic {
+            Some(isotropic) => self.transition(
+                state,
+                isotropic.birth[configuration as usize],
+                isotropic.survival[configuration as usize],
+            ),
+            None => self.next_state(state, configuration.count_ones() as usize),
+        }
+    }
+
+    fn transition(&self, state: u8, born: bool, survives: bool) -> u8 {
+        match state {
+            DEAD => {
+                if born {
+                    ALIVE
+                } else {
+                    DEAD
+                }
+            }
+            ALIVE => {
+                if survives {
+                    ALIVE
+                } else if self.states > 2 {
+                    ALIVE + 1
+                } else {
+                    DEAD
+                }
+            }
+            // Dying cells just keep on dying, whatever their neighbours are
+            dying => {
+                if dying + 1 >= self.states {
+                    DEAD
+                } else {
+                    dying + 1
+                }
+            }
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::conway()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(table) = &self.table {
+            return write!(f, "{}", table.name);
+        }
+
+        if let Some(elementary) = &self.elementary {
+            return write!(f, "W{}", elementary.number);
+        }
+
+        if let Some(isotropic) = &self.isotropic {
+            write!(
+                f,
+                "B{}/S{}",
+                hensel::format_transitions(&isotropic.birth),
+                hensel::format_transitions(&isotropic.survival)
+            )?;
+
+            if self.states > 2 {
+                write!(f, "/C{}", self.states)?;
+            }
+
+            return Ok(());
+        }
+
+        // No rulestring for these (see `to_rulestring()`), so just say what it does
+        if matches!(self.neighbourhood.shape, Shape::Custom(_)) {
+            return write!(
+                f,
+                "S{},B{} on a custom neighbourhood",
+                format_ranges(&self.survival),
+                format_ranges(&self.birth)
+            );
+        }
+
+        let suffix = match self.neighbourhood.shape {
+            Shape::Moore => "",
+            Shape::VonNeumann => "V",
+            Shape::Hexagonal => "H",
+            Shape::Triangular => "T",
+            Shape::Custom(_) => "",
+        };
+
+        if self.neighbourhood.radius == 1 && !self.include_centre {
+            write!(
+                f,
+                "B{}/S{}",
+                format_counts(&self.birth),
+                format_counts(&self.survival)
+            )?;
+
+            if self.states > 2 {
+                write!(f, "/C{}", self.states)?;
+            }
+
+            return write!(f, "{suffix}");
+        }
+
+        let shape = match self.neighbourhood.shape {
+            Shape::Moore => "NM",
+            Shape::VonNeumann => "NN",
+            Shape::Hexagonal => "NH",
+            Shape::Triangular => "NT",
+            Shape::Custom(_) => "",
+        };
+
+        write!(
+            f,
+            "R{},C{},M{},S{},B{},{shape}",
+            self.neighbourhood.radius,
+            if self.states > 2 { self.states } else { 0 },
+            self.include_centre as u8,
+            format_ranges(&self.survival),
+            format_ranges(&self.birth),
+        )
+    }
+}
+
+impl FromStr for Rule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::parse(s)
+    }
+}
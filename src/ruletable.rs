@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::Error;
+
+// Golly's .rule files: the @TABLE section lists transitions (the state of the
+// cell, of each neighbour, and the new state of the cell), with variables
+// standing for sets of states. See https://golly.sourceforge.io/Help/formats.html#rule
+
+// Wireworld (Brian Silverman, 1987), for simulating electronic circuits
+pub const WIREWORLD: &str = "\
+@RULE WireWorld
+
+0 is empty, 1 an electron head, 2 an electron tail and 3 a conductor.
+
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+
+var a={0,1,2,3}
+var b={0,1,2,3}
+var c={0,1,2,3}
+var d={0,1,2,3}
+var e={0,1,2,3}
+var f={0,1,2,3}
+var g={0,1,2,3}
+var h={0,1,2,3}
+# Anything but an electron head
+var i={0,2,3}
+var j={0,2,3}
+var k={0,2,3}
+var l={0,2,3}
+var m={0,2,3}
+var n={0,2,3}
+var o={0,2,3}
+
+# Heads become tails, and tails go back to being conductors
+1,a,b,c,d,e,f,g,h,2
+2,a,b,c,d,e,f,g,h,3
+# Conductors become heads when one or two heads are around
+3,1,i,j,k,l,m,n,o,1
+3,1,1,i,j,k,l,m,n,1
+
+@COLORS
+0 48 48 48
+1 0 128 255
+2 255 255 255
+3 255 128 0
+";
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TableNeighbourhood {
+    Moore,
+    VonNeumann,
+    Hexagonal,
+    OneDimensional,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Token {
+    State(u8),
+    // Index in `RuleTable::variables`
+    Variable(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Transition {
+    // The cell, then its neighbours in the order of the neighbourhood
+    inputs: Vec<Token>,
+    output: Token,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Symmetries {
+    // Orders in which to compare the neighbours with a transition
+    Permutations(Vec<Vec<usize>>),
+    // The neighbours can be in any order
+    Permute,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleTable {
+    pub name: String,
+    pub states: u8,
+    pub neighbourhood: TableNeighbourhood,
+    symmetries: Symmetries,
+    // The states each variable stands for, indexed by state
+    variables: Vec<Vec<bool>>,
+    transitions: Vec<Transition>,
+    // From @COLORS, if given
+    pub colors: Vec<Option<[u8; 3]>>,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+impl TableNeighbourhood {
+    /// The (dx, dy) of the neighbours, in the order the transitions list them.
+    pub fn offsets(self) -> &'static [(i32, i32)] {
+        match self {
+            TableNeighbourhood::Moore => &[
+                (0, -1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+            ],
+            TableNeighbourhood::VonNeumann => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+            // Same as the hexagonal rules (see `neighbourhood::Shape::Hexagonal`)
+            TableNeighbourhood::Hexagonal => &[(0, -1), (1, 0), (1, 1), (0, 1), (-1, 0), (-1, -1)],
+            TableNeighbourhood::OneDimensional => &[(-1, 0), (1, 0)],
+        }
+    }
+}
+
+// The orders in which to compare the neighbours, for rotateN[reflect],
+// reflect and none. The neighbours go around the cell, so rotating is
+// shifting them and reflecting is reversing them
+fn permutations(symmetries: &str, neighbourhood: TableNeighbourhood) -> Result<Symmetries, String> {
+    let count = neighbourhood.offsets().len();
+
+    if symmetries == "permute" {
+        return Ok(Symmetries::Permute);
+    }
+
+    let (rotations, reflect) = match symmetries {
+        "none" => (1, false),
+        "reflect" | "reflect_horizontal" => (1, true),
+        _ => {
+            let rest = symmetries
+                .strip_prefix("rotate")
+                .ok_or_else(|| format!("Unknown symmetries '{symmetries}'"))?;
+            let (digits, reflect) = match rest.strip_suffix("reflect") {
+                Some(digits) => (digits, true),
+                None => (rest, false),
+            };
+            let rotations: usize = digits
+                .parse()
+                .map_err(|_| format!("Unknown symmetries '{symmetries}'"))?;
+            if rotations == 0 || !count.is_multiple_of(rotations) {
+                return Err(format!(
+                    "Symmetries '{symmetries}' don't work with {count} neighbours"
+                ));
+            }
+            (rotations, reflect)
+        }
+    };
+
+    let mut orders = Vec::new();
+    for rotation in 0..rotations {
+        let shift = rotation * count / rotations;
+        orders.push((0..count).map(|i| (i + shift) % count).collect());
+
+        if reflect {
+            let mirrored = |i: usize| match neighbourhood {
+                // West and east swap places
+                TableNeighbourhood::OneDimensional => count - 1 - i,
+                // Left and right swap places, around the first (north) neighbour
+                _ => (count - i) % count,
+            };
+            orders.push((0..count).map(|i| (mirrored(i) + shift) % count).collect());
+        }
+    }
+
+    Ok(Symmetries::Permutations(orders))
+}
+
+fn parse_state(text: &str, states: u8) -> Option<u8> {
+    text.parse::<u8>().ok().filter(|s| *s < states)
+}
+
+/// Parse the text of a .rule file, only the @RULE, @TABLE and @COLORS sections are used.
+pub fn parse_rule_table(text: &str) -> Result<RuleTable, Error> {
+    let mut name = None;
+    let mut section = "";
+    let mut states = None;
+    let mut neighbourhood = TableNeighbourhood::Moore;
+    let mut symmetries = String::from("none");
+    let mut variable_names: HashMap<String, usize> = HashMap::new();
+    let mut variables: Vec<Vec<bool>> = Vec::new();
+    let mut lines = Vec::new();
+    let mut color_lines = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        // Numbered from 1, for the errors
+        let number = number + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('@') {
+            let mut words = header.split_whitespace();
+            section = words.next().unwrap_or("");
+            if section == "RULE" {
+                name = words.next().map(String::from);
+            }
+            continue;
+        }
+
+        match section {
+            "TABLE" => lines.push((number, line)),
+            "COLORS" => color_lines.push((number, line)),
+            _ => {}
+        }
+    }
+
+    let name = name.ok_or("Missing @RULE")?;
+    if lines.is_empty() {
+        return Err(Error::Invalid(format!("Rule '{name}' has no @TABLE")));
+    }
+
+    let mut transitions = Vec::new();
+    for (number, line) in lines {
+        let error = |e: &str| Error::parse(number, None, format!("{name}: {e} in '{line}'"));
+
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            match key.trim() {
+                "n_states" => {
+                    states = Some(
+                        value
+                            .parse::<u8>()
+                            .ok()
+                            .filter(|n| *n >= 2)
+                            .ok_or_else(|| error("n_states must be between 2 and 255"))?,
+                    );
+                }
+                "neighborhood" => {
+                    neighbourhood = match value {
+                        "Moore" => TableNeighbourhood::Moore,
+                        "vonNeumann" => TableNeighbourhood::VonNeumann,
+                        "hexagonal" => TableNeighbourhood::Hexagonal,
+                        "oneDimensional" => TableNeighbourhood::OneDimensional,
+                        _ => return Err(error("unsupported neighborhood")),
+                    }
+                }
+                "symmetries" => symmetries = value.to_string(),
+                _ => return Err(error("unknown setting")),
+            }
+            continue;
+        }
+
+        let n_states = states.ok_or_else(|| error("n_states must come first"))?;
+
+        if let Some(definition) = line.strip_prefix("var ") {
+            let (var_name, values) = definition
+                .split_once('=')
+                .ok_or_else(|| error("expected var name={..}"))?;
+            let values = values
+                .trim()
+                .strip_prefix('{')
+                .and_then(|v| v.strip_suffix('}'))
+                .ok_or_else(|| error("expected var name={..}"))?;
+
+            let mut set = vec![false; n_states as usize];
+            for value in values.split(',').map(|v| v.trim()) {
+                if let Some(state) = parse_state(value, n_states) {
+                    set[state as usize] = true;
+                } else if let Some(index) = variable_names.get(value) {
+                    for (state, included) in variables[*index].iter().enumerate() {
+                        set[state] |= *included;
+                    }
+                } else {
+                    return Err(error(&format!("unknown state or variable '{value}'")));
+                }
+            }
+
+            variable_names.insert(var_name.trim().to_string(), variables.len());
+            variables.push(set);
+            continue;
+        }
+
+        // With 10 states or less the commas can be left out
+        let items: Vec<String> = if line.contains(',') {
+            line.split(',').map(|t| t.trim().to_string()).collect()
+        } else {
+            line.chars()
+                .filter(|c| !c.is_whitespace())
+                .map(String::from)
+                .collect()
+        };
+
+        let expected = neighbourhood.offsets().len() + 2;
+        if items.len() != expected {
+            return Err(error(&format!("expected {expected} states")));
+        }
+
+        let mut tokens = Vec::new();
+        for item in items.iter() {
+            let token = match (
+                parse_state(item, n_states),
+                variable_names.get(item.as_str()),
+            ) {
+                (Some(state), _) => Token::State(state),
+                (None, Some(index)) => Token::Variable(*index),
+                _ => return Err(error(&format!("unknown state or variable '{item}'"))),
+            };
+            tokens.push(token);
+        }
+
+        let output = match tokens.pop() {
+            Some(output) => output,
+            None => return Err(error("missing the new state")),
+        };
+        if matches!(output, Token::Variable(_)) && !tokens.contains(&output) {
+            return Err(error(&format!(
+                "the new state '{}' must be one of the variables before it",
+                items[items.len() - 1]
+            )));
+        }
+
+        transitions.push(Transition {
+            inputs: tokens,
+            output,
+        });
+    }
+
+    let n_states = states.ok_or_else(|| format!("{name}: missing n_states"))?;
+
+    let mut colors = vec![None; n_states as usize];
+    for (number, line) in color_lines {
+        let invalid = || Error::parse(number, None, format!("{name}: invalid colour '{line}'"));
+        let numbers: Vec<u8> = line
+            .split_whitespace()
+            .map(|n| n.parse::<u8>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?;
+
+        match numbers.as_slice() {
+            [state, r, g, b] => {
+                if let Some(color) = colors.get_mut(*state as usize) {
+                    *color = Some([*r, *g, *b]);
+                }
+            }
+            // A gradient from state 1 to the last one
+            [r0, g0, b0, r1, g1, b1] => {
+                let last = n_states.max(3) as f32 - 1.0;
+                for (state, color) in colors.iter_mut().enumerate().skip(1) {
+                    let t = (state - 1) as f32 / (last - 1.0);
+                    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+                    *color = Some([mix(*r0, *r1), mix(*g0, *g1), mix(*b0, *b1)]);
+                }
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(RuleTable {
+        symmetries: permutations(&symmetries, neighbourhood).map_err(|e| format!("{name}: {e}"))?,
+        name,
+        states: n_states,
+        neighbourhood,
+        variables,
+        transitions,
+        colors,
+    })
+}
+
+pub fn load_rule_table<P: AsRef<Path>>(path: P) -> Result<RuleTable, Error> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+
+    parse_rule_table(&text).map_err(|e| e.in_file(path))
+}
+
+impl RuleTable {
+    // Whether the state fits the token, binding the variable if it's the first time we see it
+    fn fits(&self, token: Token, state: u8, bound: &mut [Option<u8>]) -> bool {
+        match token {
+            Token::State(s) => s == state,
+            Token::Variable(index) => match bound[index] {
+                Some(value) => value == state,
+                None if self.variables[index][state as usize] => {
+                    bound[index] = Some(state);
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    fn output(&self, transition: &Transition, bound: &[Option<u8>]) -> u8 {
+        match transition.output {
+            Token::State(s) => s,
+            // Checked when parsing that the variable is in the inputs
+            Token::Variable(index) => bound[index].unwrap_or(0),
+        }
+    }
+
+    // With permute, only how many neighbours are in each state matters: try
+    // every state there are neighbours left in, for each of the remaining slots
+    fn permuted(
+        &self,
+        transition: &Transition,
+        counts: &mut [u8],
+        slot: usize,
+        bound: &[Option<u8>],
+    ) -> Option<u8> {
+        if slot + 1 == transition.inputs.len() {
+            return Some(self.output(transition, bound));
+        }
+
+        for state in 0..counts.len() {
+            if counts[state] == 0 {
+                continue;
+            }
+            let mut bound = bound.to_vec();
+            if self.fits(transition.inputs[slot + 1], state as u8, &mut bound) {
+                counts[state] -= 1;
+                let output = self.permuted(transition, counts, slot + 1, &bound);
+                counts[state] += 1;
+                if output.is_some() {
+                    return output;
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The new state of a cell, given its neighbours in the order of
+    /// `TableNeighbourhood::offsets()`. Cells no transition applies to stay the same.
+    pub fn next_state(&self, state: u8, neighbours: &[u8]) -> u8 {
+        for transition in self.transitions.iter() {
+            let mut bound = vec![None; self.variables.len()];
+            if !self.fits(transition.inputs[0], state, &mut bound) {
+                continue;
+            }
+
+            match &self.symmetries {
+                Symmetries::Permute => {
+                    let mut counts = vec![0; self.states as usize];
+                    for neighbour in neighbours {
+                        counts[*neighbour as usize] += 1;
+                    }
+                    if let Some(output) = self.permuted(transition, &mut counts, 0, &bound) {
+                        return output;
+                    }
+                }
+                Symmetries::Permutations(orders) => {
+                    for order in orders {
+                        let mut bound = bound.clone();
+                        let all_fit = order.iter().enumerate().all(|(slot, i)| {
+                            self.fits(transition.inputs[slot + 1], neighbours[*i], &mut bound)
+                        });
+                        if all_fit {
+                            return self.output(transition, &bound);
+                        }
+                    }
+                }
+            }
+        }
+
+        state
+    }
+}
@@ -0,0 +1,180 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::core::Cells;
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min_x: usize,
+    pub min_y: usize,
+    pub max_x: usize,
+    pub max_y: usize,
+}
+
+impl BoundingBox {
+    pub fn width(&self) -> usize {
+        self.max_x - self.min_x + 1
+    }
+
+    pub fn height(&self) -> usize {
+        self.max_y - self.min_y + 1
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GenerationStats {
+    pub generation: i64,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub bounding_box: Option<BoundingBox>,
+    pub density: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StatsHistory {
+    pub entries: Vec<GenerationStats>,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+pub fn population(cells: &Cells, num_cells_x: usize, num_cells_y: usize) -> usize {
+    let mut population = 0;
+
+    for row in cells.rows.iter().take(num_cells_x) {
+        for cell in row.values.iter().take(num_cells_y) {
+            if cell.is_alive() {
+                population += 1;
+            }
+        }
+    }
+
+    population
+}
+
+pub fn compute_stats(
+    generation: i64,
+    previous: Option<&Cells>,
+    current: &Cells,
+    num_cells_x: usize,
+    num_cells_y: usize,
+) -> GenerationStats {
+    let mut population = 0;
+    let mut births = 0;
+    let mut deaths = 0;
+    let mut bounding_box: Option<BoundingBox> = None;
+
+    for x in 0..num_cells_x {
+        for y in 0..num_cells_y {
+            let is_alive = current.rows[x].values[y].is_alive();
+
+            // Without a previous generation to compare against,
+            // nothing has been born or has died yet
+            if let Some(previous) = previous {
+                let was_alive = previous.rows[x].values[y].is_alive();
+                if is_alive && !was_alive {
+                    births += 1;
+                }
+                if !is_alive && was_alive {
+                    deaths += 1;
+                }
+            }
+
+            if !is_alive {
+                continue;
+            }
+
+            population += 1;
+
+            bounding_box = match bounding_box {
+                None => Some(BoundingBox {
+                    min_x: x,
+                    min_y: y,
+                    max_x: x,
+                    max_y: y,
+                }),
+                Some(b) => Some(BoundingBox {
+                    min_x: b.min_x.min(x),
+                    min_y: b.min_y.min(y),
+                    max_x: b.max_x.max(x),
+                    max_y: b.max_y.max(y),
+                }),
+            };
+        }
+    }
+
+    let num_cells = num_cells_x * num_cells_y;
+    let density = if num_cells > 0 {
+        population as f32 / num_cells as f32
+    } else {
+        0.0
+    };
+
+    GenerationStats {
+        generation,
+        population,
+        births,
+        deaths,
+        bounding_box,
+        density,
+    }
+}
+
+impl StatsHistory {
+    pub fn new() -> Self {
+        StatsHistory {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, stats: GenerationStats) {
+        self.entries.push(stats);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn latest(&self) -> Option<&GenerationStats> {
+        self.entries.last()
+    }
+
+    /// The population of (at most) the last `n` recorded generations,
+    /// oldest first.
+    pub fn recent_populations(&self, n: usize) -> Vec<usize> {
+        let start = self.entries.len().saturating_sub(n);
+        self.entries[start..].iter().map(|e| e.population).collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("generation,population,births,deaths,min_x,min_y,max_x,max_y,density\n");
+
+        for entry in self.entries.iter() {
+            // An empty board has no bounding box, so leave those columns empty
+            let bounds = match entry.bounding_box {
+                Some(b) => format!("{},{},{},{}", b.min_x, b.min_y, b.max_x, b.max_y),
+                None => String::from(",,,"),
+            };
+
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                entry.generation,
+                entry.population,
+                entry.births,
+                entry.deaths,
+                bounds,
+                entry.density
+            ));
+        }
+
+        csv
+    }
+
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_csv())
+    }
+}
@@ -0,0 +1,277 @@
+use std::fs;
+use std::path::Path;
+
+use nannou::prelude::*;
+use serde::Deserialize;
+
+use crate::error::Error;
+
+// How many colours the age and activity gradients go through
+pub const GRADIENT_LENGTH: usize = 4;
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub background: Rgb8,
+    pub alive: Rgb8,
+    pub dead: Rgb8,
+    pub grid: Rgb8,
+    pub hud: Rgb8,
+    pub hud_background: Rgb8,
+    pub plot: Rgb8,
+    pub trail: Rgb8,
+    pub selection: Rgb8,
+    // Births and deaths mode: new cells, the ones that were already there,
+    // and the ones that just died (fading to the dead colour)
+    pub born: Rgb8,
+    pub survived: Rgb8,
+    pub died: Rgb8,
+    // Age mode goes from the first colour (young) to the last (old),
+    // activity mode from the dead colour to the last one
+    pub age: [Rgb8; GRADIENT_LENGTH],
+    pub activity: [Rgb8; GRADIENT_LENGTH],
+}
+
+// What a theme file looks like: every colour is optional, and
+// falls back to the one in `preset` (or the classic theme)
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    preset: Option<String>,
+    background: Option<String>,
+    alive: Option<String>,
+    dead: Option<String>,
+    grid: Option<String>,
+    hud: Option<String>,
+    hud_background: Option<String>,
+    plot: Option<String>,
+    trail: Option<String>,
+    selection: Option<String>,
+    born: Option<String>,
+    survived: Option<String>,
+    died: Option<String>,
+    age: Option<Vec<String>>,
+    activity: Option<Vec<String>>,
+}
+
+pub const CLASSIC: Theme = Theme {
+    name: "classic",
+    background: BLACK,
+    alive: RED,
+    dead: BLACK,
+    grid: WHITE,
+    hud: WHITE,
+    hud_background: BLACK,
+    plot: WHITE,
+    trail: YELLOW,
+    selection: CYAN,
+    born: rgb8(0, 255, 0),
+    survived: rgb8(128, 128, 128),
+    died: rgb8(255, 0, 0),
+    age: [
+        rgb8(255, 255, 128),
+        rgb8(255, 160, 0),
+        rgb8(200, 0, 0),
+        rgb8(80, 0, 80),
+    ],
+    activity: [
+        rgb8(40, 0, 120),
+        rgb8(200, 30, 60),
+        rgb8(255, 140, 0),
+        rgb8(255, 255, 200),
+    ],
+};
+
+pub const LIGHT: Theme = Theme {
+    name: "light",
+    background: WHITE,
+    alive: BLACK,
+    dead: WHITE,
+    grid: rgb8(190, 190, 190),
+    hud: BLACK,
+    hud_background: WHITE,
+    plot: rgb8(0, 90, 200),
+    trail: rgb8(220, 120, 0),
+    selection: rgb8(0, 150, 255),
+    born: rgb8(0, 160, 0),
+    survived: rgb8(150, 150, 150),
+    died: rgb8(220, 0, 0),
+    // Getting darker, to stand out more from the white background
+    age: [
+        rgb8(240, 200, 0),
+        rgb8(230, 120, 0),
+        rgb8(180, 0, 0),
+        rgb8(60, 0, 80),
+    ],
+    activity: [
+        rgb8(150, 120, 230),
+        rgb8(200, 30, 60),
+        rgb8(160, 70, 0),
+        rgb8(40, 0, 0),
+    ],
+};
+
+pub const HIGH_CONTRAST: Theme = Theme {
+    name: "high-contrast",
+    background: BLACK,
+    alive: WHITE,
+    dead: BLACK,
+    grid: YELLOW,
+    hud: YELLOW,
+    hud_background: BLACK,
+    plot: YELLOW,
+    trail: CYAN,
+    selection: MAGENTA,
+    born: CYAN,
+    survived: WHITE,
+    died: RED,
+    age: [WHITE, YELLOW, CYAN, MAGENTA],
+    activity: [BLUE, MAGENTA, YELLOW, WHITE],
+};
+
+// Based on the Okabe-Ito palette, which stays distinguishable
+// with the common kinds of colour blindness
+pub const COLOUR_BLIND: Theme = Theme {
+    name: "colour-blind",
+    background: rgb8(0, 0, 0),
+    alive: rgb8(230, 159, 0),
+    dead: rgb8(0, 0, 0),
+    grid: rgb8(86, 180, 233),
+    hud: rgb8(240, 228, 66),
+    hud_background: rgb8(0, 0, 0),
+    plot: rgb8(86, 180, 233),
+    trail: rgb8(0, 158, 115),
+    selection: rgb8(204, 121, 167),
+    // Blue and vermillion instead of green and red
+    born: rgb8(86, 180, 233),
+    survived: rgb8(128, 128, 128),
+    died: rgb8(213, 94, 0),
+    // Viridis, which only changes in lightness for those who can't tell hues apart
+    age: [
+        rgb8(253, 231, 37),
+        rgb8(53, 183, 121),
+        rgb8(49, 104, 142),
+        rgb8(68, 1, 84),
+    ],
+    activity: [
+        rgb8(68, 1, 84),
+        rgb8(49, 104, 142),
+        rgb8(53, 183, 121),
+        rgb8(253, 231, 37),
+    ],
+};
+
+pub const PRESETS: [Theme; 4] = [CLASSIC, LIGHT, HIGH_CONTRAST, COLOUR_BLIND];
+
+// Functions
+// ----------------------------------------------------------------------------
+const fn rgb8(red: u8, green: u8, blue: u8) -> Rgb8 {
+    Rgb8 {
+        red,
+        green,
+        blue,
+        standard: std::marker::PhantomData,
+    }
+}
+
+/// Parse a "#rrggbb" colour, exactly that: one '#' and 6 hex digits.
+pub fn parse_hex_color(s: &str) -> Result<Rgb8, Error> {
+    let invalid = || Error::Invalid(format!("Invalid colour '{s}', expected #rrggbb"));
+
+    // from_str_radix alone would take a sign too ("+f")
+    let hex = s
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or_else(invalid)?;
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    Ok(rgb8(channel(0)?, channel(2)?, channel(4)?))
+}
+
+pub fn preset(name: &str) -> Option<Theme> {
+    PRESETS.iter().find(|t| t.name == name).copied()
+}
+
+pub fn parse_theme(text: &str) -> Result<Theme, Error> {
+    let file: ThemeFile = toml::from_str(text)?;
+
+    let mut theme = match file.preset.as_deref() {
+        Some(name) => preset(name).ok_or_else(|| format!("Unknown theme preset '{name}'"))?,
+        None => CLASSIC,
+    };
+
+    let overrides = [
+        (&file.background, &mut theme.background),
+        (&file.alive, &mut theme.alive),
+        (&file.dead, &mut theme.dead),
+        (&file.grid, &mut theme.grid),
+        (&file.hud, &mut theme.hud),
+        (&file.hud_background, &mut theme.hud_background),
+        (&file.plot, &mut theme.plot),
+        (&file.trail, &mut theme.trail),
+        (&file.selection, &mut theme.selection),
+        (&file.born, &mut theme.born),
+        (&file.survived, &mut theme.survived),
+        (&file.died, &mut theme.died),
+    ];
+    for (value, color) in overrides {
+        if let Some(value) = value {
+            *color = parse_hex_color(value)?;
+        }
+    }
+
+    let gradients = [
+        ("age", &file.age, &mut theme.age),
+        ("activity", &file.activity, &mut theme.activity),
+    ];
+    for (key, values, colors) in gradients {
+        if let Some(values) = values {
+            if values.len() != GRADIENT_LENGTH {
+                return Err(Error::Invalid(format!(
+                    "Expected {GRADIENT_LENGTH} colours for '{key}', got {}",
+                    values.len()
+                )));
+            }
+            for (value, color) in values.iter().zip(colors.iter_mut()) {
+                *color = parse_hex_color(value)?;
+            }
+        }
+    }
+
+    theme.name = "custom";
+    Ok(theme)
+}
+
+pub fn load_theme<P: AsRef<Path>>(path: P) -> Result<Theme, Error> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+
+    parse_theme(&text).map_err(|e| e.in_file(path))
+}
+
+/// Either the name of a preset, or the path to a theme file.
+pub fn theme_from_arg(arg: &str) -> Result<Theme, Error> {
+    match preset(arg) {
+        Some(theme) => Ok(theme),
+        None => load_theme(arg),
+    }
+}
+
+impl Theme {
+    /// The preset after this one (used to cycle through them).
+    pub fn next_preset(&self) -> Theme {
+        let index = PRESETS.iter().position(|t| t.name == self.name);
+        match index {
+            Some(i) => PRESETS[(i + 1) % PRESETS.len()],
+            None => PRESETS[0],
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        CLASSIC
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use crate::census::{self, Point};
+use crate::core::Cells;
+
+// The slowest spaceships we look for (glider and *WSS are period 4)
+pub const MAX_SPACESHIP_PERIOD: usize = 8;
+// Anything bigger than this is treated as debris, not as a spaceship
+pub const MAX_SPACESHIP_CELLS: usize = 64;
+// How many periods an object has to keep moving for before we report it
+pub const CONFIRMATION_PERIODS: usize = 3;
+// ..and for at least this many generations, whatever its period
+pub const CONFIRMATION_GENERATIONS: usize = 16;
+// Keep the trails from growing forever
+pub const MAX_TRAIL_LENGTH: usize = 512;
+// Spaceships can come apart in some of their phases (the front of a *WSS is
+// a single cell every other generation), so cells this close are one object
+pub const OBJECT_SPACING: i32 = 2;
+
+// Data structures
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Velocity {
+    pub period: i64,
+    pub dx: i32,
+    pub dy: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackedObject {
+    pub id: usize,
+    pub velocity: Velocity,
+    pub population: usize,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub sightings: usize,
+    // Centre of the object (in cells) every time it was seen
+    pub trail: Vec<(f32, f32)>,
+}
+
+// Shape (normalised) -> where its copies are on the board (top-left corners)
+type Generation = HashMap<Vec<Point>, Vec<Point>>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Tracker {
+    history: VecDeque<Generation>,
+    // Moved a few times, but not for long enough to be reported yet
+    candidates: Vec<TrackedObject>,
+    pub objects: Vec<TrackedObject>,
+}
+
+// Functions
+// ----------------------------------------------------------------------------
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn centre(points: &[Point]) -> (f32, f32) {
+    let n = points.len().max(1) as f32;
+    let sum_x: i32 = points.iter().map(|p| p.0).sum();
+    let sum_y: i32 = points.iter().map(|p| p.1).sum();
+
+    (sum_x as f32 / n, sum_y as f32 / n)
+}
+
+fn origin(points: &[Point]) -> Point {
+    let min_x = points.iter().map(|p| p.0).min().unwrap_or(0);
+    let min_y = points.iter().map(|p| p.1).min().unwrap_or(0);
+
+    (min_x, min_y)
+}
+
+impl Velocity {
+    pub fn is_orthogonal(&self) -> bool {
+        self.dx == 0 || self.dy == 0
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        self.dx.abs() == self.dy.abs()
+    }
+
+    /// The speed in the usual notation, eg: "c/4" or "2c/5".
+    pub fn speed(&self) -> String {
+        let distance = self.dx.abs().max(self.dy.abs()) as i64;
+        let divisor = gcd(distance, self.period).max(1);
+        let (n, d) = (distance / divisor, self.period / divisor);
+
+        match (n, d) {
+            (1, 1) => String::from("c"),
+            (1, d) => format!("c/{d}"),
+            (n, 1) => format!("{n}c"),
+            (n, d) => format!("{n}c/{d}"),
+        }
+    }
+}
+
+impl fmt::Display for Velocity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let direction = if self.is_orthogonal() {
+            "orthogonal"
+        } else if self.is_diagonal() {
+            "diagonal"
+        } else {
+            "oblique"
+        };
+
+        write!(
+            f,
+            "{} {direction} (moves {},{} every {} generations)",
+            self.speed(),
+            self.dx,
+            self.dy,
+            self.period
+        )
+    }
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Tracker::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.candidates.clear();
+        self.objects.clear();
+    }
+
+    /// The objects which have been seen moving in the last few generations.
+    pub fn active_objects(&self, generation: i64) -> impl Iterator<Item = &TrackedObject> {
+        self.objects
+            .iter()
+            .filter(move |o| generation - o.last_seen <= MAX_SPACESHIP_PERIOD as i64)
+    }
+
+    /// Look for objects that reappeared somewhere else with the same shape.
+    /// Returns the indices (in `objects`) of the objects confirmed as moving
+    /// in this generation.
+    pub fn observe(
+        &mut self,
+        generation: i64,
+        cells: &Cells,
+        num_cells_x: usize,
+        num_cells_y: usize,
+    ) -> Vec<usize> {
+        let points = census::live_points(cells, num_cells_x, num_cells_y);
+        let components: Vec<Vec<Point>> = census::components_within(&points, OBJECT_SPACING)
+            .into_iter()
+            .filter(|c| c.len() <= MAX_SPACESHIP_CELLS)
+            .collect();
+
+        let mut current = Generation::new();
+        for component in components.iter() {
+            current
+                .entry(census::normalise(component))
+                .or_default()
+                .push(origin(component));
+        }
+
+        let mut new_objects = Vec::new();
+
+        for component in components.iter() {
+            let shape = census::normalise(component);
+            let position = origin(component);
+
+            if let Some(velocity) = self.find_velocity(&shape, position, &current) {
+                let (cx, cy) = centre(component);
+                if let Some(index) =
+                    self.update_track(generation, velocity, component.len(), (cx, cy))
+                {
+                    new_objects.push(index);
+                }
+            }
+        }
+
+        self.history.push_front(current);
+        self.history.truncate(MAX_SPACESHIP_PERIOD);
+
+        new_objects
+    }
+
+    fn find_velocity(
+        &self,
+        shape: &[Point],
+        position: Point,
+        current: &Generation,
+    ) -> Option<Velocity> {
+        for (i, past) in self.history.iter().enumerate() {
+            let period = i as i32 + 1;
+
+            let origins = match past.get(shape) {
+                Some(origins) => origins,
+                None => continue,
+            };
+
+            // Same shape in the same place: a still life or an oscillator
+            if origins.contains(&position) {
+                return None;
+            }
+
+            let vacated = |o: &Point| {
+                !current
+                    .get(shape)
+                    .map(|now| now.contains(o))
+                    .unwrap_or(false)
+            };
+
+            // Nothing can travel faster than light, and the place it came
+            // from must be empty now (otherwise it is just a neighbouring copy)
+            let found = origins.iter().find(|o| {
+                let dx = position.0 - o.0;
+                let dy = position.1 - o.1;
+                dx.abs() <= period && dy.abs() <= period && vacated(o)
+            });
+
+            if let Some(o) = found {
+                return Some(Velocity {
+                    period: period as i64,
+                    dx: position.0 - o.0,
+                    dy: position.1 - o.1,
+                });
+            }
+        }
+
+        None
+    }
+
+    fn update_track(
+        &mut self,
+        generation: i64,
+        velocity: Velocity,
+        population: usize,
+        position: (f32, f32),
+    ) -> Option<usize> {
+        // An object can't have moved further than its speed allows since
+        // we last saw it (plus a bit, as the centre wobbles between phases)
+        let is_same_object = |o: &TrackedObject| {
+            let elapsed = (generation - o.last_seen) as f32;
+            let (x, y) = o.trail.last().copied().unwrap_or(position);
+            let distance = (x - position.0).abs().max((y - position.1).abs());
+
+            o.velocity == velocity
+                && elapsed <= MAX_SPACESHIP_PERIOD as f32
+                && distance <= elapsed + 2.0
+        };
+
+        let sighted = |o: &mut TrackedObject| {
+            o.last_seen = generation;
+            o.sightings += 1;
+            o.trail.push(position);
+            if o.trail.len() > MAX_TRAIL_LENGTH {
+                o.trail.remove(0);
+            }
+        };
+
+        if let Some(object) = self.objects.iter_mut().find(|o| is_same_object(o)) {
+            sighted(object);
+            return None;
+        }
+
+        // Forget the candidates that didn't keep on moving
+        self.candidates
+            .retain(|o| generation - o.last_seen <= MAX_SPACESHIP_PERIOD as i64);
+
+        let index = match self.candidates.iter().position(is_same_object) {
+            Some(index) => {
+                sighted(&mut self.candidates[index]);
+                index
+            }
+            None => {
+                self.candidates.push(TrackedObject {
+                    id: 0,
+                    velocity,
+                    population,
+                    first_seen: generation,
+                    last_seen: generation,
+                    sightings: 1,
+                    trail: vec![position],
+                });
+                self.candidates.len() - 1
+            }
+        };
+
+        // Bits of debris in a busy soup can look like they moved once or
+        // twice, so only trust objects that kept going for a few periods
+        let candidate = &self.candidates[index];
+        let needed =
+            (CONFIRMATION_PERIODS * velocity.period as usize).max(CONFIRMATION_GENERATIONS);
+        if candidate.sightings < needed {
+            return None;
+        }
+
+        let mut object = self.candidates.remove(index);
+        object.id = self.objects.len();
+        self.objects.push(object);
+
+        Some(self.objects.len() - 1)
+    }
+
+    /// How many objects were seen moving with each velocity.
+    pub fn summary(&self) -> String {
+        let mut counts: Vec<(Velocity, usize)> = Vec::new();
+
+        for object in self.objects.iter() {
+            match counts.iter_mut().find(|(v, _)| *v == object.velocity) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((object.velocity, 1)),
+            }
+        }
+        counts.sort_by_key(|c| std::cmp::Reverse(c.1));
+
+        let mut text = format!("{} moving object(s):\n", self.objects.len());
+        for (velocity, count) in counts {
+            text.push_str(&format!("{count:>8} x {velocity}\n"));
+        }
+
+        text
+    }
+}
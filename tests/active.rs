@@ -0,0 +1,131 @@
+use std::time::{Duration, Instant};
+
+use game_of_life::active::{ActiveRegion, TILE_SIZE};
+use game_of_life::core::{self, Cells, DEFAULT_NUM_CELLS_X};
+use game_of_life::rules::Rule;
+
+const SIZE: usize = DEFAULT_NUM_CELLS_X;
+
+fn assert_same_states(a: &Cells, b: &Cells, generation: usize) {
+    for x in 0..SIZE {
+        for y in 0..SIZE {
+            let (a, b) = (a.rows[x].values[y], b.rows[x].values[y]);
+            assert_eq!(
+                (a.state, a.age, a.time_since_death, a.activity),
+                (b.state, b.age, b.time_since_death, b.activity),
+                "cell {x},{y} differs at generation {generation}"
+            );
+        }
+    }
+}
+
+fn assert_active_matches(rulestring: &str, generations: usize) {
+    let rule = Rule::parse(rulestring).unwrap();
+    let mut full = core::init_cells(SIZE, SIZE, true);
+    let mut sparse = full.clone();
+    let mut active = ActiveRegion::new();
+
+    for generation in 1..=generations {
+        full = core::step_cells(&full, SIZE, SIZE, &rule);
+        sparse = active.step(&sparse, SIZE, SIZE, &rule, 2);
+        assert_same_states(&full, &sparse, generation);
+
+        // Draw on both boards now and then, like the mouse does between generations
+        if generation % 25 == 0 {
+            let (x, y) = (generation % SIZE, SIZE / 2);
+            for board in [&mut full, &mut sparse] {
+                let cell = &mut board.rows[x].values[y];
+                *cell = core::next_cell(*cell, 1);
+            }
+            active.edited(x, y);
+        }
+    }
+}
+
+// A settled board: a grid of blocks, and a single blinker
+fn still_board() -> Cells {
+    let mut cells = core::init_cells(SIZE, SIZE, false);
+    for x in (4..SIZE - 4).step_by(16) {
+        for y in (4..SIZE - 4).step_by(16) {
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                cells.rows[x + dx].values[y + dy] = core::ALIVE_CELL;
+            }
+        }
+    }
+    for dx in 0..3 {
+        cells.rows[60 + dx].values[60] = core::ALIVE_CELL;
+    }
+    cells
+}
+
+#[test]
+fn life_is_the_same_as_stepping_every_cell() {
+    assert_active_matches("life", 200);
+}
+
+#[test]
+fn generations_rules_are_the_same_as_stepping_every_cell() {
+    assert_active_matches("brians-brain", 100);
+}
+
+#[test]
+fn larger_than_life_is_the_same_as_stepping_every_cell() {
+    assert_active_matches("bosco", 60);
+}
+
+#[test]
+fn rule_tables_are_the_same_as_stepping_every_cell() {
+    assert_active_matches("wireworld", 60);
+}
+
+#[test]
+fn rules_where_cells_are_born_alone_are_the_same_as_stepping_every_cell() {
+    assert_active_matches("B0/S8", 40);
+}
+
+#[test]
+fn only_the_tiles_around_changes_are_stepped() {
+    let rule = Rule::conway();
+    let mut cells = still_board();
+    let mut active = ActiveRegion::new();
+
+    // Everything to begin with
+    cells = active.step(&cells, SIZE, SIZE, &rule, 1);
+    assert_eq!(active.evaluated, SIZE * SIZE);
+
+    // Then just the blinker, at most 3x3 tiles around it
+    for _ in 0..10 {
+        cells = active.step(&cells, SIZE, SIZE, &rule, 1);
+        assert!(active.evaluated <= 9 * TILE_SIZE * TILE_SIZE);
+    }
+}
+
+// Timing depends on the machine and whatever else runs at the same time,
+// so only when asked for: cargo test --release -- --ignored
+#[test]
+#[ignore]
+fn a_mostly_still_board_steps_much_faster() {
+    let generations = 100;
+
+    let time = |step: &mut dyn FnMut(&Cells) -> Cells| -> Duration {
+        let mut cells = still_board();
+        let start = Instant::now();
+        for _ in 0..generations {
+            cells = step(&cells);
+        }
+        start.elapsed()
+    };
+
+    // Life, and the same rule written so that the neighbours are counted all at once
+    for rulestring in ["life", "R1,C0,M0,S2..3,B3..3,NM"] {
+        let rule = Rule::parse(rulestring).unwrap();
+        let full = time(&mut |cells| core::step_cells(cells, SIZE, SIZE, &rule));
+        let mut active = ActiveRegion::new();
+        let sparse = time(&mut |cells| active.step(cells, SIZE, SIZE, &rule, 1));
+
+        assert!(
+            sparse * 3 < full,
+            "expected a large speedup with {rulestring}, got {full:?} vs {sparse:?}"
+        );
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashSet;
+
+use nannou::prelude::*;
+
+use game_of_life::active::ActiveRegion;
+use game_of_life::cli;
+use game_of_life::core::{self, Cells, RenderMode};
+use game_of_life::cursor::Cursor;
+use game_of_life::geometry::{Geometry, Layout};
+use game_of_life::plane::Plane;
+use game_of_life::rules::Rule;
+use game_of_life::{render, rle, theme};
+
+// A 16:9 board, and one that's taller than it's wide
+const SIZES: [(usize, usize); 2] = [(320, 180), (90, 200)];
+const GENERATIONS: usize = 40;
+
+const GLIDER: &str = "x = 3, y = 3\nbo$2bo$3o!";
+
+fn assert_same_cells(a: &Cells, b: &Cells, (width, height): (usize, usize), what: &str) {
+    for x in 0..width {
+        for y in 0..height {
+            assert_eq!(
+                a.rows[x].values[y].state, b.rows[x].values[y].state,
+                "cell {x},{y} of the {width}x{height} board differs ({what})"
+            );
+        }
+    }
+}
+
+#[test]
+fn boards_are_as_wide_and_tall_as_asked() {
+    for (width, height) in SIZES {
+        let cells = core::init_cells(width, height, true);
+        assert_eq!(cells.rows.len(), width);
+        assert!(cells.rows.iter().all(|row| row.values.len() == height));
+    }
+}
+
+// Far from the edges, the board steps like the plane (which has none)
+#[test]
+fn gliders_fly_the_same_on_the_board_and_the_plane() {
+    let glider = rle::parse_rle(GLIDER).unwrap();
+
+    for rulestring in ["life", "B3/S23-q", "R1,C0,M0,S2..3,B3..3,NM"] {
+        let rule = Rule::parse(rulestring).unwrap();
+
+        for (width, height) in SIZES {
+            // Heading down-right, towards the far corner of the board
+            let (left, top) = (width as i32 - 30, height as i32 - 30);
+            let mut cells = core::init_cells(width, height, false);
+            rle::place_pattern(&glider, &mut cells, width, height, left, top);
+            let mut plane = Plane::from_pattern(&rle::Pattern {
+                position: Some((left, top)),
+                ..glider.clone()
+            });
+
+            for _ in 0..GENERATIONS {
+                cells = core::step_cells(&cells, width, height, &rule);
+                plane = plane.step(&rule);
+            }
+
+            let mut on_board = rle::pattern_from_cells(&cells, width, height, &rule);
+            let mut on_plane = plane.to_pattern(&rule);
+            on_board.cells.sort_unstable();
+            on_plane.cells.sort_unstable();
+            assert_eq!(
+                on_board.cells, on_plane.cells,
+                "{rulestring} on {width}x{height}"
+            );
+
+            // 40 generations is 10 cells down and right
+            let (min_x, min_y, _, _) = plane.bounds().unwrap();
+            assert_eq!((min_x, min_y), (left + 10, top + 10));
+        }
+    }
+}
+
+#[test]
+fn every_way_of_stepping_agrees_on_rectangular_boards() {
+    for rulestring in ["life", "brians-brain", "tlife", "hex-life"] {
+        let rule = Rule::parse(rulestring).unwrap();
+
+        for (width, height) in SIZES {
+            let start = core::init_cells(width, height, true);
+            let (mut single, mut parallel, mut sparse) = (start.clone(), start.clone(), start);
+            let mut active = ActiveRegion::new();
+
+            for _ in 0..GENERATIONS {
+                single = core::step_cells(&single, width, height, &rule);
+                parallel = core::step_cells_in_parallel(&parallel, width, height, &rule, 3);
+                sparse = active.step(&sparse, width, height, &rule, 3);
+            }
+
+            assert_same_cells(&single, &parallel, (width, height), rulestring);
+            assert_same_cells(&single, &sparse, (width, height), rulestring);
+        }
+    }
+}
+
+#[test]
+fn patterns_round_trip_through_rectangular_boards() {
+    let glider = rle::parse_rle(GLIDER).unwrap();
+
+    for (width, height) in SIZES {
+        let (cells, clipped) = rle::centred_pattern(&glider, width, height);
+        assert_eq!(clipped, 0);
+        // The middle of the board, not of a square one
+        assert!(cells.rows[width / 2].values[height / 2].is_alive());
+
+        let pattern = rle::pattern_from_cells(&cells, width, height, &Rule::default());
+        let text = rle::write_rle(&pattern).unwrap();
+        assert!(text.starts_with("x = 3, y = 3"), "{text}");
+        assert_eq!(rle::parse_rle(&text).unwrap().cells, glider.cells);
+    }
+
+    // Too wide for a tall board, so the sides get clipped
+    let wide = rle::parse_rle("x = 100, y = 1\n100o!").unwrap();
+    let (_, clipped) = rle::centred_pattern(&wide, 90, 200);
+    assert_eq!(clipped, 10);
+}
+
+#[test]
+fn painting_finds_the_cells_in_every_corner() {
+    let (width, height) = SIZES[0];
+    let layout = Layout::new(
+        Geometry::Square,
+        (width * core::CELL_SIZE) as f32,
+        (height * core::CELL_SIZE) as f32,
+        width,
+        height,
+        core::CELL_SIZE,
+    );
+
+    for (x, y) in [
+        (0, 0),
+        (width - 1, 0),
+        (0, height - 1),
+        (width - 1, height - 1),
+    ] {
+        let centre = layout.centre(x as f32, y as f32);
+        assert_eq!(layout.cell_at(centre), Some((x, y)));
+    }
+    // Just past the bottom-right corner
+    let outside = layout.centre(width as f32, height as f32 - 1.0);
+    assert_eq!(layout.cell_at(outside), None);
+}
+
+// Drawing doesn't count as a generation: no age or activity from going over a cell again
+#[test]
+fn painted_cells_start_fresh() {
+    let (width, height) = (16, 16);
+    let mut cells = core::init_cells(width, height, false);
+    let mut cursor = Cursor::new(2, 3);
+    cursor.move_by(2, 1, width, height, true);
+
+    for _ in 0..3 {
+        cursor.fill(&mut cells, 1);
+    }
+    let cell = cells.rows[3].values[4];
+    assert_eq!((cell.state, cell.age, cell.activity), (1, 0, 0));
+
+    // Toggling them off and on again is a new cell too, not one that died and came back
+    cursor.toggle(&mut cells, 1);
+    cursor.toggle(&mut cells, 1);
+    for x in 2..=4 {
+        for y in 3..=4 {
+            let cell = cells.rows[x].values[y];
+            assert_eq!((cell.state, cell.age, cell.activity), (1, 0, 0), "{x},{y}");
+        }
+    }
+
+    // Then it ages like any other
+    let rule = Rule::conway();
+    cells = core::step_cells(&cells, width, height, &rule);
+    assert_eq!(cells.rows[2].values[3].age, 1);
+
+    let mut plane = Plane::default();
+    for _ in 0..3 {
+        plane.set(-5, 7, 1);
+    }
+    let cell = plane.get(-5, 7);
+    assert_eq!((cell.state, cell.age, cell.activity), (1, 0, 0));
+    plane.set(-5, 7, 2);
+    assert_eq!(plane.get(-5, 7).state, 2);
+    assert_eq!(plane.get(-5, 7).age, 0);
+
+    // Loading a pattern is the same as drawing it, on the board and on the plane
+    let single = rle::parse_rle("x = 1, y = 1\no!").unwrap();
+    let (cells, _clipped) = rle::centred_pattern(&single, width, height);
+    let cell = cells.rows[7].values[7];
+    assert_eq!((cell.state, cell.age, cell.activity), (1, 0, 0));
+    let cell = Plane::from_pattern(&single).get(0, 0);
+    assert_eq!((cell.state, cell.age, cell.activity), (1, 0, 0));
+}
+
+#[test]
+fn the_board_fits_the_window_and_the_command_line() {
+    assert_eq!(core::board_size(1280.0, 720.0, 4), (320, 180));
+    assert_eq!(core::board_size(1283.0, 722.0, 4), (320, 180));
+
+    let args = |size: &str| vec!["--size".to_string(), size.to_string()].into_iter();
+    assert_eq!(cli::parse_args(args("320x180")).unwrap().size, (320, 180));
+    for size in ["320", "0x180", "320xabc"] {
+        assert!(cli::parse_args(args(size)).is_err(), "{size}");
+    }
+}
+
+#[test]
+fn shrinking_and_growing_the_window_keeps_the_board() {
+    let (width, height) = (320, 180);
+    let cells = core::init_cells(width, height, true);
+
+    for geometry in [Geometry::Square, Geometry::Hexagonal, Geometry::Triangular] {
+        let layout = Layout::new(geometry, 1280.0, 720.0, width, height, 4);
+        let triangles = render::board_mesh(
+            &cells,
+            &layout,
+            RenderMode::Alive,
+            &theme::CLASSIC,
+            &Rule::default(),
+        );
+
+        // Down to nothing (like when minimised), then back again
+        let shrunk = layout.resized(1.0, 1.0, 4);
+        assert_eq!((shrunk.num_cells_x, shrunk.num_cells_y), (width, height));
+        let grown = shrunk.resized(1280.0, 720.0, 4);
+        assert_eq!(grown, layout, "{geometry}");
+
+        let again = render::board_mesh(
+            &cells,
+            &grown,
+            RenderMode::Alive,
+            &theme::CLASSIC,
+            &Rule::default(),
+        );
+        assert_eq!(
+            render::rasterise(&triangles, theme::CLASSIC.background, 1280, 720),
+            render::rasterise(&again, theme::CLASSIC.background, 1280, 720),
+            "{geometry}"
+        );
+    }
+}
+
+#[test]
+fn a_smaller_window_shows_less_of_the_same_board() {
+    let layout = Layout::new(Geometry::Square, 1280.0, 720.0, 320, 180, 4);
+    let smaller = layout.resized(640.0, 360.0, 4);
+
+    // Same size squares, from the same top-left corner
+    assert_eq!(smaller.centre(0.0, 0.0), pt2(-318.0, 178.0));
+    assert_eq!(smaller.cell_at(pt2(-318.0, 178.0)), Some((0, 0)));
+    assert_eq!(smaller.cell_at(pt2(318.0, -178.0)), Some((159, 89)));
+
+    // The rest is past the edges of the window, but still there
+    let corner = smaller.centre(319.0, 179.0);
+    assert!(corner.x > 320.0 && corner.y < -180.0, "{corner:?}");
+    assert_eq!(smaller.cell_at(corner), Some((319, 179)));
+}
+
+#[test]
+fn the_grid_goes_around_the_board_not_the_window() {
+    let layout = Layout::new(Geometry::Square, 1280.0, 720.0, 320, 180, 4);
+
+    for (width, height) in [(640.0, 360.0), (1920.0, 1080.0), (1.0, 1.0)] {
+        let resized = layout.resized(width, height, 4);
+        let lines = core::create_grid_for(&resized);
+
+        // A line before every row and column, and one after the last
+        assert_eq!(lines.len(), (180 + 1) + (320 + 1), "{width}x{height}");
+
+        // From the top-left corner of the first cell to the bottom-right one of the last
+        let first = resized.centre(0.0, 0.0) - pt2(2.0, -2.0);
+        let last = resized.centre(319.0, 179.0) + pt2(2.0, -2.0);
+        for line in lines.iter() {
+            let (x, y) = (line.start_x.min(line.end_x), line.start_y.max(line.end_y));
+            assert!(x >= first.x && y <= first.y, "{line:?}");
+            let (x, y) = (line.start_x.max(line.end_x), line.start_y.min(line.end_y));
+            assert!(x <= last.x && y >= last.y, "{line:?}");
+        }
+        assert_eq!(
+            lines.iter().map(|l| l.end_x).fold(f32::MIN, f32::max),
+            last.x
+        );
+        assert_eq!(
+            lines.iter().map(|l| l.end_y).fold(f32::MAX, f32::min),
+            last.y
+        );
+    }
+
+    // A board set on the command line, smaller than the window
+    let small = Layout::new(Geometry::Square, 1280.0, 720.0, 10, 5, 4);
+    let lines = core::create_grid_for(&small);
+    assert_eq!(lines.len(), 6 + 11);
+    assert_eq!(lines[0].end_x - lines[0].start_x, 40.0);
+}
+
+// Hexagons and triangles, in a window they don't exactly fit in
+const SHAPES: [Geometry; 2] = [Geometry::Hexagonal, Geometry::Triangular];
+
+#[test]
+fn every_hexagon_and_triangle_can_be_clicked() {
+    let (width, height) = (9, 6);
+
+    for geometry in SHAPES {
+        let layout = Layout::new(geometry, 640.0, 480.0, width, height, 4);
+        // Well inside the cell, away from its edges and corners
+        let inside = match geometry {
+            Geometry::Hexagonal => layout.cell_width * 0.4,
+            _ => layout.cell_width * 0.2,
+        };
+
+        for x in 0..width {
+            for y in 0..height {
+                let centre = layout.centre(x as f32, y as f32);
+                assert_eq!(layout.cell_at(centre), Some((x, y)), "{geometry} {x},{y}");
+
+                for i in 0..12 {
+                    let angle = (i as f32 * 30.0 + 15.0).to_radians();
+                    let point = centre + pt2(angle.cos(), angle.sin()) * inside;
+                    assert_eq!(layout.cell_at(point), Some((x, y)), "{geometry} {point:?}");
+                }
+            }
+        }
+
+        // Where the next cell would be, past each edge of the board
+        for (x, y) in [
+            (width as f32, 0.0),
+            (-1.0, 2.0),
+            (3.0, -1.0),
+            (3.0, height as f32),
+        ] {
+            let outside = layout.centre(x, y);
+            assert_eq!(layout.cell_at(outside), None, "{geometry} {x},{y}");
+        }
+    }
+}
+
+#[test]
+fn grid_lines_are_the_edges_of_the_cells_once_each() {
+    // Rounded, and the same whichever way round the line goes
+    let edge = |a: Point2, b: Point2| {
+        let round = |p: Point2| ((p.x * 100.0).round() as i64, (p.y * 100.0).round() as i64);
+        let (a, b) = (round(a), round(b));
+        (a.min(b), a.max(b))
+    };
+
+    // Squares, hexagons and triangles in a 4x2 board
+    for (geometry, expected) in [
+        (Geometry::Square, 4 * 3 + 2 * 5),
+        (Geometry::Hexagonal, 6 * 8 - (3 * 2 + 4 + 3)),
+        (Geometry::Triangular, 3 * 8 - (3 * 2 + 2)),
+    ] {
+        let layout = Layout::new(geometry, 640.0, 480.0, 4, 2, 16);
+        let lines = layout.grid_lines();
+        assert_eq!(lines.len(), expected, "{geometry}");
+
+        let drawn: HashSet<_> = lines
+            .iter()
+            .map(|l| edge(pt2(l.start_x, l.start_y), pt2(l.end_x, l.end_y)))
+            .collect();
+        assert_eq!(drawn.len(), lines.len(), "{geometry} has lines drawn twice");
+
+        let mut sides = HashSet::new();
+        for x in 0..4 {
+            for y in 0..2 {
+                let corners = layout.polygon(x, y);
+                for (i, corner) in corners.iter().enumerate() {
+                    sides.insert(edge(*corner, corners[(i + 1) % corners.len()]));
+                }
+            }
+        }
+        assert_eq!(drawn, sides, "{geometry}");
+    }
+}
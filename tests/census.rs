@@ -0,0 +1,112 @@
+use game_of_life::census::{self, Census, CensusEntry, CensusFormat};
+use game_of_life::core::{self, Cells};
+use game_of_life::rle;
+use game_of_life::rules::Rule;
+
+const SIZE: usize = 64;
+
+// Top-left corner and pattern of everything on the board
+const OBJECTS: [(i32, i32, &str); 7] = [
+    (5, 5, "x = 2, y = 2\n2o$2o!"),
+    (40, 8, "x = 2, y = 2\n2o$2o!"),
+    (20, 20, "x = 3, y = 1\n3o!"),
+    (50, 30, "x = 1, y = 3\no$o$o!"),
+    (10, 45, "x = 3, y = 1\n3o!"),
+    (30, 40, "x = 3, y = 3\nbo$2bo$3o!"),
+    // A snake, which isn't one of the known objects
+    (50, 50, "x = 4, y = 2\n2obo$ob2o!"),
+];
+
+fn soup() -> Cells {
+    let mut cells = core::init_cells(SIZE, SIZE, false);
+    for (x, y, rle) in OBJECTS {
+        let pattern = rle::parse_rle(rle).unwrap();
+        rle::place_pattern(&pattern, &mut cells, SIZE, SIZE, x, y);
+    }
+    cells
+}
+
+fn entry(name: &str, apgcode: Option<&str>, count: usize) -> CensusEntry {
+    CensusEntry {
+        name: name.to_string(),
+        apgcode: apgcode.map(String::from),
+        count,
+    }
+}
+
+#[test]
+fn every_object_is_counted_once() {
+    // Blinkers repeat every 2 generations and the glider every 4
+    let census = census::take_census(&soup(), SIZE, SIZE, 4, &Rule::conway());
+
+    assert_eq!(census.soups, 1);
+    assert_eq!(
+        census.entries,
+        [
+            entry("blinker", Some("xp2_7"), 3),
+            entry("block", Some("xs4_33"), 2),
+            entry("glider", Some("xq4_153"), 1),
+            entry("unknown(6 cells)", None, 1),
+        ]
+    );
+}
+
+#[test]
+fn objects_are_found_in_any_phase() {
+    let rule = Rule::conway();
+    let mut cells = soup();
+
+    for _ in 0..3 {
+        cells = core::step_cells(&cells, SIZE, SIZE, &rule);
+        let census = census::take_census(&cells, SIZE, SIZE, 4, &rule);
+        assert_eq!(census.entries.len(), 4);
+        assert_eq!(census.entries[0], entry("blinker", Some("xp2_7"), 3));
+        assert_eq!(census.entries[2], entry("glider", Some("xq4_153"), 1));
+    }
+}
+
+#[test]
+fn unknown_objects_are_not_given_an_apgcode() {
+    let census = census::take_census(&soup(), SIZE, SIZE, 4, &Rule::conway());
+
+    assert_eq!(
+        census.format(CensusFormat::Text),
+        "Census of 1 soup(s), 7 object(s):\n\
+         xp2_7            blinker                         3\n\
+         xs4_33           block                           2\n\
+         xq4_153          glider                          1\n\
+         -                unknown(6 cells)                1\n"
+    );
+    assert_eq!(
+        census.format(CensusFormat::Json),
+        "{\n  \"soups\": 1,\n  \"census\": [\n\
+         \x20   {\"apgcode\": \"xp2_7\", \"name\": \"blinker\", \"count\": 3},\n\
+         \x20   {\"apgcode\": \"xs4_33\", \"name\": \"block\", \"count\": 2},\n\
+         \x20   {\"apgcode\": \"xq4_153\", \"name\": \"glider\", \"count\": 1},\n\
+         \x20   {\"apgcode\": null, \"name\": \"unknown(6 cells)\", \"count\": 1}\n\
+         \x20 ]\n}\n"
+    );
+}
+
+#[test]
+fn censuses_of_several_soups_add_up() {
+    let mut total = Census::new();
+    total.add_object("unknown(7 cells)", None, 2);
+    total.add_object("block", Some("xs4_33"), 5);
+
+    let census = census::take_census(&soup(), SIZE, SIZE, 4, &Rule::conway());
+    total.merge(&census);
+    total.merge(&census);
+
+    assert_eq!(total.soups, 2);
+    assert_eq!(
+        total.entries,
+        [
+            entry("block", Some("xs4_33"), 9),
+            entry("blinker", Some("xp2_7"), 6),
+            entry("glider", Some("xq4_153"), 2),
+            entry("unknown(6 cells)", None, 2),
+            entry("unknown(7 cells)", None, 2),
+        ]
+    );
+}
@@ -0,0 +1,128 @@
+use game_of_life::core::{self, Cells};
+use game_of_life::detect::{CycleDetector, Stabilisation, StabilisationReport, StableAction};
+use game_of_life::rle;
+use game_of_life::rules::Rule;
+
+const SIZE: usize = 32;
+
+fn board(rle: &str) -> Cells {
+    let (cells, _clipped) = rle::centred_pattern(&rle::parse_rle(rle).unwrap(), SIZE, SIZE);
+    cells
+}
+
+// Step until the detector says the board settled, or give up
+fn run(
+    mut detector: CycleDetector,
+    mut cells: Cells,
+    generations: i64,
+) -> Option<(i64, StabilisationReport)> {
+    let rule = Rule::conway();
+
+    for generation in 0..generations {
+        if let Some(report) = detector.observe(generation, &cells, SIZE, SIZE) {
+            return Some((generation, report));
+        }
+        cells = core::step_cells(&cells, SIZE, SIZE, &rule);
+    }
+
+    None
+}
+
+fn report(kind: Stabilisation, generation: i64) -> StabilisationReport {
+    StabilisationReport { kind, generation }
+}
+
+#[test]
+fn boards_that_die_out_are_extinct() {
+    let detector = CycleDetector::default();
+    assert_eq!(
+        run(detector.clone(), board("x = 0, y = 0\n!"), 10),
+        Some((0, report(Stabilisation::Extinct, 0)))
+    );
+    // A domino has no cells with enough neighbours
+    assert_eq!(
+        run(detector.clone(), board("x = 2, y = 1\n2o!"), 10),
+        Some((1, report(Stabilisation::Extinct, 1)))
+    );
+    // A diagonal line shrinks from both ends
+    assert_eq!(
+        run(detector, board("x = 5, y = 5\no$bo$2bo$3bo$4bo!"), 10),
+        Some((3, report(Stabilisation::Extinct, 3)))
+    );
+}
+
+#[test]
+fn still_lifes_are_found_the_generation_after() {
+    let detector = CycleDetector::default();
+    assert_eq!(
+        run(detector.clone(), board("x = 2, y = 2\n2o$2o!"), 10),
+        Some((1, report(Stabilisation::StillLife, 0)))
+    );
+    // Three cells of a block become the block
+    assert_eq!(
+        run(detector, board("x = 2, y = 2\n2o$o!"), 10),
+        Some((2, report(Stabilisation::StillLife, 1)))
+    );
+}
+
+#[test]
+fn blinkers_have_period_2() {
+    let detector = CycleDetector::default();
+    assert_eq!(
+        run(detector.clone(), board("x = 3, y = 1\n3o!"), 10),
+        Some((2, report(Stabilisation::Oscillator { period: 2 }, 0)))
+    );
+    // Four cells in a T become a traffic light after 9 generations
+    assert_eq!(
+        run(detector, board("x = 3, y = 2\n3o$bo!"), 20),
+        Some((11, report(Stabilisation::Oscillator { period: 2 }, 9)))
+    );
+}
+
+#[test]
+fn periods_longer_than_the_window_are_not_found() {
+    // Remembers only the last generation, so it can't see a blinker repeat
+    assert_eq!(
+        run(CycleDetector::new(1), board("x = 3, y = 1\n3o!"), 20),
+        None
+    );
+
+    // But still sees a still life, and starts over after a reset
+    let mut detector = CycleDetector::new(1);
+    let block = board("x = 2, y = 2\n2o$2o!");
+    assert_eq!(detector.observe(0, &block, SIZE, SIZE), None);
+    detector.reset();
+    assert_eq!(detector.observe(1, &block, SIZE, SIZE), None);
+    assert_eq!(
+        detector.observe(2, &block, SIZE, SIZE),
+        Some(report(Stabilisation::StillLife, 1))
+    );
+}
+
+#[test]
+fn reports_say_what_happened() {
+    let reports = [
+        (report(Stabilisation::Extinct, 5), "extinct at generation 5"),
+        (
+            report(Stabilisation::StillLife, 7),
+            "still life from generation 7",
+        ),
+        (
+            report(Stabilisation::Oscillator { period: 2 }, 10),
+            "period 2 oscillator from generation 10",
+        ),
+    ];
+    for (report, text) in reports {
+        assert_eq!(report.to_string(), text);
+    }
+
+    for action in [
+        StableAction::Continue,
+        StableAction::Pause,
+        StableAction::Reset,
+    ] {
+        assert_eq!(action.to_string().parse::<StableAction>().unwrap(), action);
+    }
+    assert_eq!(StableAction::Reset.next(), StableAction::Continue);
+    assert!("stop".parse::<StableAction>().is_err());
+}
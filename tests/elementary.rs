@@ -0,0 +1,145 @@
+use game_of_life::core;
+use game_of_life::elementary::{self, Boundary, Elementary, InitialRow};
+use game_of_life::error::Error;
+use game_of_life::rules::Rule;
+
+const BOUNDARIES: [Boundary; 3] = [Boundary::Dead, Boundary::Alive, Boundary::Wrap];
+
+fn row(text: &str) -> Vec<bool> {
+    text.chars().map(|c| c == 'o').collect()
+}
+
+fn text(row: &[bool]) -> String {
+    row.iter()
+        .map(|alive| if *alive { 'o' } else { '.' })
+        .collect()
+}
+
+// Every generation from the first row, as text
+fn run(number: u8, boundary: Boundary, first: &str, generations: usize) -> Vec<String> {
+    let rule = Elementary { number, boundary };
+    let mut rows = vec![row(first)];
+    for _ in 0..generations {
+        rows.push(rule.next_row(rows.last().unwrap()));
+    }
+    rows.iter().map(|r| text(r)).collect()
+}
+
+#[test]
+fn rule_30_from_a_seed() {
+    let expected = [
+        ".....o.....",
+        "....ooo....",
+        "...oo..o...",
+        "..oo.oooo..",
+        ".oo..o...o.",
+        "oo.oooo.ooo",
+    ];
+    // Nothing reaches the ends in the first 5 generations with dead or
+    // wrapping ends, so those look the same as on an endless row
+    for boundary in [Boundary::Dead, Boundary::Wrap] {
+        assert_eq!(run(30, boundary, expected[0], 5), expected, "{boundary}");
+    }
+
+    // With alive ends, 100 (4) comes alive at the left and 001 (1) at the right
+    let alive = run(30, Boundary::Alive, "...o...", 1);
+    assert_eq!(alive, ["...o...", "o.ooo.o"]);
+}
+
+#[test]
+fn rule_90_from_a_seed() {
+    let expected = [
+        ".......o.......",
+        "......o.o......",
+        ".....o...o.....",
+        "....o.o.o.o....",
+        "...o.......o...",
+        "..o.o.....o.o..",
+        ".o...o...o...o.",
+        "o.o.o.o.o.o.o.o",
+    ];
+    for boundary in [Boundary::Dead, Boundary::Wrap] {
+        assert_eq!(run(90, boundary, expected[0], 7), expected, "{boundary}");
+    }
+
+    // Each cell is its neighbours XORed: alive ends switch the cells next to them
+    assert_eq!(
+        run(90, Boundary::Alive, ".....", 2),
+        [".....", "o...o", "oo.oo"]
+    );
+}
+
+#[test]
+fn the_ends_are_what_the_boundary_says() {
+    // Rule 90 on a ring of 8 cells dies out after 4 generations, on a line the
+    // dead cell past the end keeps one of them alive
+    let wrapped = run(90, Boundary::Wrap, "...o....", 4);
+    assert_eq!(wrapped[3], "o.o.o.o.");
+    assert_eq!(wrapped[4], "........");
+    let line = run(90, Boundary::Dead, "...o....", 4);
+    assert_eq!(line[4], ".......o");
+
+    // Rule 204 copies the row whatever the ends are, rule 0 clears it
+    for boundary in BOUNDARIES {
+        assert_eq!(run(204, boundary, "o.oo..o", 1)[1], "o.oo..o");
+        assert_eq!(run(0, boundary, "o.oo..o", 1)[1], ".......");
+    }
+
+    // Rule 1 brings cells with nothing around them alive: past the ends that
+    // depends on what's there (and on the other end, when wrapping)
+    assert_eq!(run(1, Boundary::Dead, "o...", 1)[1], "..oo");
+    assert_eq!(run(1, Boundary::Alive, "o...", 1)[1], "..o.");
+    assert_eq!(run(1, Boundary::Wrap, "...o", 1)[1], ".o..");
+}
+
+#[test]
+fn the_board_keeps_the_generations_before() {
+    let (width, height) = (11, 8);
+    let rule = Rule::parse("W30").unwrap();
+    let mut cells = elementary::initial_cells(&InitialRow::Seed, width, height);
+    for _ in 0..5 {
+        cells = core::step_cells(&cells, width, height, &rule);
+    }
+
+    // The last generation at the bottom, the first one 5 rows up
+    let board_row = |y: usize| {
+        text(
+            &(0..width)
+                .map(|x| cells.rows[x].values[y].is_alive())
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_eq!(board_row(height - 1), "oo.oooo.ooo");
+    assert_eq!(board_row(height - 6), ".....o.....");
+    assert_eq!(board_row(height - 7), "...........");
+}
+
+#[test]
+fn rows_and_boundaries_are_read_from_text() {
+    assert_eq!("seed".parse::<InitialRow>().unwrap(), InitialRow::Seed);
+    assert_eq!("random".parse::<InitialRow>().unwrap(), InitialRow::Random);
+    assert_eq!(
+        "..o1*b0".parse::<InitialRow>().unwrap(),
+        InitialRow::Cells(vec![false, false, true, true, true, false, false])
+    );
+
+    for bad in ["", "Seed", "..x..", "o o", "11 "] {
+        match bad.parse::<InitialRow>() {
+            Err(Error::Invalid(message)) => assert!(message.contains("Invalid row"), "{message}"),
+            other => panic!("expected '{bad}' to be invalid, got {other:?}"),
+        }
+    }
+
+    for boundary in BOUNDARIES {
+        assert_eq!(boundary.to_string().parse::<Boundary>().unwrap(), boundary);
+    }
+    assert!("periodic".parse::<Boundary>().is_err());
+
+    // Centred on the board, and clipped when wider than it
+    let cells = elementary::initial_cells(&"ooo.o".parse().unwrap(), 9, 2);
+    let bottom: Vec<bool> = (0..9).map(|x| cells.rows[x].values[1].is_alive()).collect();
+    assert_eq!(text(&bottom), "..ooo.o..");
+    let cells = elementary::initial_cells(&"o.ooo.o".parse().unwrap(), 3, 1);
+    let bottom: Vec<bool> = (0..3).map(|x| cells.rows[x].values[0].is_alive()).collect();
+    assert_eq!(text(&bottom), "ooo");
+}
@@ -0,0 +1,271 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use game_of_life::active::ActiveRegion;
+use game_of_life::census;
+use game_of_life::core::{self, Cells};
+use game_of_life::detect::{CycleDetector, Stabilisation, StabilisationReport};
+use game_of_life::headless;
+use game_of_life::plane::{Plane, Point};
+use game_of_life::rle::{self, Pattern};
+use game_of_life::rules::{self, Rule};
+
+// Big enough that nothing here gets near the edges
+const SIZE: usize = 128;
+const MIDDLE: i32 = SIZE as i32 / 2;
+
+const BLINKER: &str = "x = 3, y = 1\n3o!";
+const BLINKER_UPRIGHT: &str = "x = 1, y = 3\no$o$o!";
+const GLIDER: &str = "x = 3, y = 3\nbo$2bo$3o!";
+const R_PENTOMINO: &str = "x = 3, y = 3\nb2o$2o$bo!";
+
+// x, y and state of every cell that isn't dead
+type States = Vec<(i32, i32, u8)>;
+
+// Rules with no births from nothing, with 2 and more states and other neighbourhoods
+const RULES: [&str; 6] = [
+    "life",
+    "highlife",
+    "brians-brain",
+    "hex-life",
+    "B3/S23-q",
+    "R1,C0,M0,S2..3,B3..3,NM",
+];
+
+// The same order for everyone, so they can be compared
+fn board_cells(cells: &Cells, (left, top): Point) -> States {
+    let mut points = Vec::new();
+    for (x, row) in cells.rows.iter().enumerate() {
+        for (y, cell) in row.values.iter().enumerate() {
+            if cell.state != rules::DEAD {
+                points.push((x as i32 - left, y as i32 - top, cell.state));
+            }
+        }
+    }
+    points.sort_unstable();
+    points
+}
+
+fn plane_cells(plane: &Plane, (left, top): Point) -> States {
+    let mut points: States = plane
+        .cells
+        .iter()
+        .map(|((x, y), cell)| (x - left, y - top, cell.state))
+        .collect();
+    points.sort_unstable();
+    points
+}
+
+fn pattern_cells(pattern: &Pattern, (dx, dy): Point) -> States {
+    let mut points: States = pattern
+        .cells
+        .iter()
+        .map(|(x, y, state)| (x + dx, y + dy, *state))
+        .collect();
+    points.sort_unstable();
+    points
+}
+
+// The pattern after some generations, relative to where its top-left corner
+// started, for every way there is of stepping it
+fn evolve(pattern: &Pattern, rule: &Rule, generations: usize) -> Vec<(&'static str, States)> {
+    let corner = (MIDDLE, MIDDLE);
+    let mut start = core::init_cells(SIZE, SIZE, false);
+    rle::place_pattern(pattern, &mut start, SIZE, SIZE, corner.0, corner.1);
+
+    let (mut single, mut parallel, mut sparse) = (start.clone(), start.clone(), start);
+    let mut active = ActiveRegion::new();
+    let mut plane = Plane::from_pattern(&Pattern {
+        position: Some((0, 0)),
+        ..pattern.clone()
+    });
+
+    for _ in 0..generations {
+        single = core::step_cells(&single, SIZE, SIZE, rule);
+        parallel = core::step_cells_in_parallel(&parallel, SIZE, SIZE, rule, 3);
+        sparse = active.step(&sparse, SIZE, SIZE, rule, 3);
+        plane = plane.step(rule);
+    }
+
+    vec![
+        ("one thread", board_cells(&single, corner)),
+        ("three threads", board_cells(&parallel, corner)),
+        ("active region", board_cells(&sparse, corner)),
+        ("infinite plane", plane_cells(&plane, (0, 0))),
+    ]
+}
+
+// Stepping `start` gives `expected`, moved by `shift`, everywhere
+fn assert_evolves_to(start: &str, generations: usize, expected: &str, shift: Point) {
+    let start = rle::parse_rle(start).unwrap();
+    let expected = pattern_cells(&rle::parse_rle(expected).unwrap(), shift);
+
+    for (backend, cells) in evolve(&start, &Rule::default(), generations) {
+        assert_eq!(
+            cells, expected,
+            "after {generations} generations ({backend})"
+        );
+    }
+}
+
+// A random soup, its top-left corner at 0,0
+fn soup(width: usize, height: usize) -> Pattern {
+    let mut soup = Plane::random(width, height).to_pattern(&Rule::default());
+    soup.position = Some((0, 0));
+    soup
+}
+
+#[test]
+fn blinkers_have_period_2() {
+    assert_evolves_to(BLINKER, 1, BLINKER_UPRIGHT, (1, -1));
+    assert_evolves_to(BLINKER, 2, BLINKER, (0, 0));
+    assert_evolves_to(BLINKER, 101, BLINKER_UPRIGHT, (1, -1));
+
+    let blinker = rle::parse_rle(BLINKER).unwrap();
+    let (cells, _clipped) = rle::centred_pattern(&blinker, SIZE, SIZE);
+    let run = headless::run_until_stable(cells, SIZE, SIZE, &Rule::default(), 1, 100, None);
+    assert_eq!(
+        run.stabilisation,
+        Some(StabilisationReport {
+            kind: Stabilisation::Oscillator { period: 2 },
+            generation: 0,
+        })
+    );
+}
+
+#[test]
+fn gliders_move_one_cell_diagonally_every_4_generations() {
+    assert_evolves_to(GLIDER, 4, GLIDER, (1, 1));
+    assert_evolves_to(GLIDER, 40, GLIDER, (10, 10));
+}
+
+#[test]
+fn the_r_pentomino_stabilises_at_generation_1103() {
+    let rule = Rule::default();
+    let mut plane = Plane::from_pattern(&rle::parse_rle(R_PENTOMINO).unwrap());
+
+    // Only what's left around where it started, the 6 gliders fly off forever
+    let debris_hash = |plane: &Plane| {
+        let mut debris: Vec<(Point, u8)> = plane
+            .cells
+            .iter()
+            .filter(|((x, y), _cell)| x.abs() <= 60 && y.abs() <= 60)
+            .map(|(point, cell)| (*point, cell.state))
+            .collect();
+        debris.sort_unstable();
+
+        let mut hasher = DefaultHasher::new();
+        debris.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let mut detector = CycleDetector::default();
+    let mut generation = 0;
+    let report = loop {
+        if let Some(report) = detector.observe_hash(generation, debris_hash(&plane), false) {
+            break report;
+        }
+        assert!(generation < 1200, "still going at generation {generation}");
+        plane = plane.step(&rule);
+        generation += 1;
+    };
+
+    assert_eq!(
+        report,
+        StabilisationReport {
+            kind: Stabilisation::Oscillator { period: 2 },
+            generation: 1103,
+        }
+    );
+    // Gliders included
+    assert_eq!(plane.population(), 116);
+}
+
+#[test]
+fn empty_boards_stay_empty() {
+    let empty = rle::parse_rle("x = 0, y = 0\n!").unwrap();
+
+    for rulestring in RULES {
+        let rule = Rule::parse(rulestring).unwrap();
+        for (backend, cells) in evolve(&empty, &rule, 50) {
+            assert!(cells.is_empty(), "{rulestring} ({backend})");
+        }
+    }
+}
+
+// There's no wrapping board to try it on, but the plane has no edges at all,
+// and on the board a soup far enough from the edges can't tell where it is
+#[test]
+fn moving_a_pattern_moves_what_it_becomes() {
+    let generations: usize = 40;
+    let positions = [(-37, 5), (120, -64), (1, 1)];
+
+    for rulestring in RULES {
+        let rule = Rule::parse(rulestring).unwrap();
+        let start = soup(16, 16);
+
+        let mut here = Plane::from_pattern(&start);
+        let mut there: Vec<Plane> = positions
+            .into_iter()
+            .map(|position| {
+                Plane::from_pattern(&Pattern {
+                    position: Some(position),
+                    ..start.clone()
+                })
+            })
+            .collect();
+        for _ in 0..generations {
+            here = here.step(&rule);
+            there = there.iter().map(|plane| plane.step(&rule)).collect();
+        }
+        for (plane, position) in there.iter().zip(positions) {
+            assert_eq!(
+                plane_cells(plane, position),
+                plane_cells(&here, (0, 0)),
+                "{rulestring} moved to {position:?} on the plane"
+            );
+        }
+
+        // From the top-left to the bottom-right of the middle of the board
+        let margin = generations as i32 + 1;
+        let boards: Vec<States> = [margin, SIZE as i32 - margin - 16]
+            .into_iter()
+            .map(|corner| {
+                let mut cells = core::init_cells(SIZE, SIZE, false);
+                rle::place_pattern(&start, &mut cells, SIZE, SIZE, corner, corner);
+                for _ in 0..generations {
+                    cells = core::step_cells(&cells, SIZE, SIZE, &rule);
+                }
+                board_cells(&cells, (corner, corner))
+            })
+            .collect();
+        assert_eq!(boards[0], boards[1], "{rulestring} moved on the board");
+        assert_eq!(boards[0], plane_cells(&here, (0, 0)), "{rulestring}");
+    }
+}
+
+#[test]
+fn every_backend_agrees_on_random_soups() {
+    for rulestring in RULES {
+        let rule = Rule::parse(rulestring).unwrap();
+
+        for _ in 0..3 {
+            let start = soup(24, 24);
+            let results = evolve(&start, &rule, 40);
+            let (_, expected) = &results[0];
+            for (backend, cells) in &results[1..] {
+                assert_eq!(cells, expected, "{rulestring} ({backend})");
+            }
+
+            // The census has its own Life, for working out the known objects
+            if rulestring == "life" {
+                let mut points: Vec<Point> = start.cells.iter().map(|(x, y, _)| (*x, *y)).collect();
+                for _ in 0..40 {
+                    points = census::step_points(&points);
+                }
+                let life: Vec<Point> = expected.iter().map(|(x, y, _)| (*x, *y)).collect();
+                assert_eq!(points, life, "census");
+            }
+        }
+    }
+}
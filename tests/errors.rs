@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::PathBuf;
+
+use nannou::prelude::*;
+
+use game_of_life::core;
+use game_of_life::error::Error;
+use game_of_life::{cli, keymap, neighbourhood, rle, ruletable, theme};
+
+// A file of our own in the temp directory, so the tests don't step on each other
+fn temp_file(name: &str, text: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("game-of-life-{}-{name}", std::process::id()));
+    fs::write(&path, text).unwrap();
+    path
+}
+
+fn assert_parse_error(error: Error, line: usize, column: Option<usize>) {
+    match error {
+        Error::Parse {
+            line: Some(l),
+            column: c,
+            ..
+        } => assert_eq!((l, c), (line, column)),
+        other => panic!("expected a parse error on line {line}, got {other:?}"),
+    }
+}
+
+#[test]
+fn rle_errors_point_at_the_character() {
+    let text = "#N glider\nx = 3, y = 3\nbo$2bo$\n3oz!";
+    assert_parse_error(rle::parse_rle(text).unwrap_err(), 4, Some(3));
+
+    let error = rle::parse_rle("x = 3\nbo!").unwrap_err();
+    assert_parse_error(error, 1, None);
+}
+
+#[test]
+fn errors_from_files_say_which_file() {
+    let path = temp_file("broken.rle", "x = 3, y = 3\nbo$2bo$3o?!");
+    let error = rle::load_rle(&path).unwrap_err();
+    let text = error.to_string();
+    fs::remove_file(&path).unwrap();
+
+    assert!(
+        text.starts_with(&format!("{}:2:10: ", path.display())),
+        "{text}"
+    );
+}
+
+#[test]
+fn missing_files_are_io_errors() {
+    let path = std::env::temp_dir().join("game-of-life-not-there.rle");
+    match rle::load_rle(&path) {
+        Err(Error::Io { path: p, source }) => {
+            assert_eq!(p, path);
+            assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+        }
+        other => panic!("expected an io error, got {other:?}"),
+    }
+}
+
+#[test]
+fn config_errors_have_a_line_and_column() {
+    // Themes and keymaps are TOML
+    let error = theme::parse_theme("preset = \"classic\"\nalive = #ff0000\n").unwrap_err();
+    assert_parse_error(error, 2, Some(9));
+    let error = keymap::parse_keymap("start = \"s\"\nsave = [\"w\"\n").unwrap_err();
+    assert!(
+        matches!(error, Error::Parse { line: Some(_), .. }),
+        "{error:?}"
+    );
+
+    // Rule tables and neighbourhoods, only the line
+    let table =
+        "@RULE Broken\n@TABLE\nn_states:2\nneighborhood:Moore\n0,0,0,0,0,0,0,0,0,1\n0,1,2\n";
+    assert_parse_error(ruletable::parse_rule_table(table).unwrap_err(), 6, None);
+    let mask = "xxx\nx.x\nxx\n";
+    assert_parse_error(neighbourhood::parse_mask(mask).unwrap_err(), 3, None);
+}
+
+#[test]
+fn bad_values_are_invalid_not_panics() {
+    let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+    for bad in [
+        &["--rule", "B9/S23"][..],
+        &["--geometry", "octagon"],
+        &["--theme", "#nope"],
+        &["--threads", "0"],
+        &["--frobnicate"],
+    ] {
+        let result = cli::parse_args(args(bad).into_iter());
+        assert!(result.is_err(), "{bad:?}");
+    }
+}
+
+#[test]
+fn snapping_works_with_fewer_grid_points_than_asked() {
+    let points = [pt2(0.0, 0.0), pt2(4.0, 0.0)];
+    let closest = core::closest_n_points(pt2(3.0, 1.0), &points, 4);
+    assert_eq!(closest, vec![pt2(4.0, 0.0), pt2(0.0, 0.0)]);
+
+    assert!(core::closest_n_points(pt2(f32::NAN, 0.0), &points, 1).len() == 1);
+    assert!(core::closest_n_points(pt2(0.0, 0.0), &[], 4).is_empty());
+}
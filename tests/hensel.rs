@@ -0,0 +1,146 @@
+use game_of_life::core;
+use game_of_life::hensel::{self, configuration_letters};
+use game_of_life::rules::Rule;
+
+// The letters for each number of neighbours, from the wiki
+const LETTERS: [&str; 9] = [
+    "",
+    "ce",
+    "ceaikn",
+    "ceaiknjqry",
+    "ceaiknjqrtwyz",
+    "ceaiknjqry",
+    "ceaikn",
+    "ce",
+    "",
+];
+
+fn parse(part: &str) -> hensel::Transitions {
+    hensel::parse_transitions(part, part).unwrap()
+}
+
+#[test]
+fn every_letter_is_read_as_its_configurations() {
+    let table = configuration_letters();
+
+    for (count, letters) in LETTERS.iter().enumerate() {
+        let mut used: Vec<char> = (0..256)
+            .filter(|c| (*c as u8).count_ones() as usize == count)
+            .filter_map(|c| table[c])
+            .collect();
+        used.sort();
+        used.dedup();
+        let mut expected: Vec<char> = letters.chars().collect();
+        expected.sort();
+        assert_eq!(used, expected, "{count}");
+
+        // "2n" is exactly the configurations labelled 2n
+        for letter in letters.chars() {
+            let transitions = parse(&format!("{count}{letter}"));
+            for c in 0..256 {
+                let labelled = (c as u8).count_ones() as usize == count && table[c] == Some(letter);
+                assert_eq!(transitions[c], labelled, "{count}{letter} {c:08b}");
+            }
+        }
+    }
+
+    // No letters for all or nothing
+    assert_eq!(table[0], None);
+    assert_eq!(table[255], None);
+}
+
+#[test]
+fn letters_cover_every_configuration_once() {
+    let table = configuration_letters();
+    let with = |count: u32, letter: char| {
+        (0..256)
+            .filter(|c| (*c as u8).count_ones() == count && table[*c] == Some(letter))
+            .count()
+    };
+
+    // Corners and edges can be rotated 4 ways, the rest 8, or just 2
+    // when they're opposite each other (2i and 2n)
+    let two: Vec<usize> = "ceaikn".chars().map(|l| with(2, l)).collect();
+    assert_eq!(two, [4, 4, 8, 2, 8, 2]);
+    assert_eq!(two.iter().sum::<usize>(), 28);
+
+    for count in 1..8 {
+        let total: usize = LETTERS[count as usize]
+            .chars()
+            .map(|l| with(count, l))
+            .sum();
+        let configurations = (0..256).filter(|c| (*c as u8).count_ones() == count);
+        assert_eq!(total, configurations.count(), "{count}");
+    }
+}
+
+#[test]
+fn transitions_are_written_the_way_they_are_read() {
+    for part in ["3", "2n3", "23-q", "2-a", "34q", "4-tz", "1c2k5-y78", "0"] {
+        assert_eq!(hensel::format_transitions(&parse(part)), part);
+    }
+
+    // Any choice of letters, written the shortest way, reads back the same
+    for (count, all) in LETTERS.iter().enumerate().take(8).skip(1) {
+        for skipped in all.chars() {
+            for part in [
+                format!("{count}{skipped}"),
+                format!("{count}-{skipped}"),
+                format!("{count}{}", all.replace(skipped, "")),
+            ] {
+                let transitions = parse(&part);
+                let written = hensel::format_transitions(&transitions);
+                assert_eq!(
+                    parse(&written),
+                    transitions,
+                    "{part} was written as {written}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn only_letters_that_exist_are_read() {
+    for part in ["2x", "9", "2-", "1a", "-q", "4-tx"] {
+        assert!(hensel::parse_transitions(part, part).is_err(), "{part}");
+    }
+    assert_eq!(parse(""), [false; 256]);
+
+    // And the same through the rule
+    assert!(Rule::parse("B2x/S23").is_err());
+    assert!(Rule::parse("B3/S1a").is_err());
+}
+
+#[test]
+fn a_q_shaped_cell_dies_without_3q_survival() {
+    // The cell in the middle, with its N, NE and SW neighbours (3q)
+    let size = 5;
+    let mut cells = core::init_cells(size, size, false);
+    for (x, y) in [(2, 2), (2, 1), (3, 1), (1, 3)] {
+        cells.rows[x].values[y] = core::ALIVE_CELL;
+    }
+
+    let q = hensel::configuration(&cells, size, size, 2, 2);
+    assert_eq!(q, 0b00100110);
+    assert_eq!(configuration_letters()[q as usize], Some('q'));
+
+    let life = core::step_cells(&cells, size, size, &Rule::conway());
+    assert!(life.rows[2].values[2].is_alive());
+
+    let rule = Rule::parse("B3/S23-q").unwrap();
+    let next = core::step_cells(&cells, size, size, &rule);
+    assert!(!next.rows[2].values[2].is_alive());
+
+    // The other cells only see 1 or 2 neighbours, same as in Life, and
+    // the dead ones with 3 (like 2,2 does) are born either way
+    for x in 0..size {
+        for y in (0..size).filter(|y| (x, *y) != (2, 2)) {
+            assert_eq!(
+                next.rows[x].values[y].is_alive(),
+                life.rows[x].values[y].is_alive(),
+                "{x},{y}"
+            );
+        }
+    }
+}
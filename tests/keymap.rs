@@ -0,0 +1,108 @@
+use nannou::event::ModifiersState;
+use nannou::prelude::Key;
+
+use game_of_life::keymap::{self, Action, Binding, Keymap};
+
+fn binding(key: &str) -> Binding {
+    key.parse().unwrap()
+}
+
+#[test]
+fn the_default_keymap_has_no_conflicts() {
+    let keymap = Keymap::default();
+    assert_eq!(keymap.conflicts(), Vec::new());
+
+    for (action, bindings) in &keymap.bindings {
+        assert!(!bindings.is_empty(), "{action} has no keys");
+    }
+}
+
+#[test]
+fn keys_are_written_the_way_they_are_read() {
+    // Letters are shown in capitals, like on the keyboard
+    for (key, shown) in [
+        ("s", "S"),
+        ("ctrl+s", "ctrl+S"),
+        ("ctrl+alt+shift+f5", "ctrl+alt+shift+F5"),
+        ("+", "+"),
+        ("ctrl++", "ctrl++"),
+        ("numpad-minus", "numpad-minus"),
+        ("space", "space"),
+    ] {
+        assert_eq!(binding(key).to_string(), shown);
+        assert_eq!(binding(shown), binding(key));
+    }
+    assert_eq!(binding("Ctrl+S"), binding("ctrl+s"));
+
+    for key in ["", "ctrl+", "hyper+s", "nope"] {
+        assert!(key.parse::<Binding>().is_err(), "{key}");
+    }
+}
+
+#[test]
+fn keymap_files_rebind_only_what_they_mention() {
+    let keymap = keymap::parse_keymap("start = \"space\"\nsave = [\"ctrl+s\", \"f2\"]\n").unwrap();
+
+    assert_eq!(keymap.keys(Action::Start), &[binding("space")]);
+    assert_eq!(
+        keymap.keys(Action::Save),
+        &[binding("ctrl+s"), binding("f2")]
+    );
+    assert_eq!(
+        keymap.keys(Action::Load),
+        Keymap::default().keys(Action::Load)
+    );
+
+    assert!(keymap::parse_keymap("fly = \"f\"").is_err());
+    assert!(keymap::parse_keymap("start = 3").is_err());
+}
+
+#[test]
+fn conflicts_are_reported_when_loading() {
+    // 'c' already clears the board
+    let error = keymap::parse_keymap("save = \"c\"")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("'C'"), "{error}");
+
+    // The cursor takes over its keys, so those are fine
+    assert!(keymap::parse_keymap("cursor-toggle = \"s\"").is_ok());
+    // But not twice
+    assert!(keymap::parse_keymap("cursor-toggle = \"left\"").is_err());
+}
+
+#[test]
+fn the_cursor_and_the_plane_only_get_keys_when_in_use() {
+    let keymap = Keymap::default();
+    let left = binding("left");
+
+    assert_eq!(keymap.action_for(left, false, false), None);
+    assert_eq!(keymap.action_for(left, true, false), Some(Action::PanLeft));
+    assert_eq!(
+        keymap.action_for(left, false, true),
+        Some(Action::CursorLeft)
+    );
+
+    // Shift is for selecting with the cursor
+    let pressed = Binding::pressed(Key::Left, ModifiersState::SHIFT);
+    assert_eq!(
+        keymap.action_for(pressed, false, true),
+        Some(Action::CursorLeft)
+    );
+    // Unless it's part of the key
+    let pressed = Binding::pressed(Key::Slash, ModifiersState::SHIFT);
+    assert_eq!(
+        keymap.action_for(pressed, false, false),
+        Some(Action::ToggleHelp)
+    );
+}
+
+#[test]
+fn the_help_lists_the_active_keymap() {
+    let keymap = keymap::parse_keymap("start = \"ctrl+enter\"").unwrap();
+    let help = keymap.help(false);
+
+    assert!(help.iter().any(|line| line.starts_with("ctrl+enter")));
+    assert!(!help.iter().any(|line| line.contains("Fit")));
+    assert!(keymap.help(true).len() > help.len());
+}
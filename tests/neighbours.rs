@@ -0,0 +1,361 @@
+use std::collections::HashSet;
+
+use game_of_life::core::{self, CellIndex, Cells};
+use game_of_life::geometry::{Geometry, Layout};
+use game_of_life::neighbourhood::Neighbourhood;
+use game_of_life::rules::Rule;
+
+// Thin, flat, square and odd boards, down to a single cell
+const SIZES: [(usize, usize); 9] = [
+    (1, 1),
+    (1, 6),
+    (6, 1),
+    (2, 2),
+    (3, 3),
+    (2, 5),
+    (5, 7),
+    (7, 5),
+    (16, 9),
+];
+
+// Every cell at most one step away (but not the cell itself), the slow way
+fn reference_neighbours(x: usize, y: usize, width: usize, height: usize) -> Vec<CellIndex> {
+    let mut neighbours = Vec::new();
+    for nx in 0..width {
+        for ny in 0..height {
+            let (dx, dy) = (nx.abs_diff(x), ny.abs_diff(y));
+            if dx.max(dy) == 1 {
+                neighbours.push(CellIndex { x: nx, y: ny });
+            }
+        }
+    }
+    neighbours.sort();
+    neighbours
+}
+
+// B3/S23 on a board with dead cells all around, with none of the app's code
+fn reference_step(alive: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let (width, height) = (alive.len(), alive[0].len());
+    let mut next = vec![vec![false; height]; width];
+
+    for x in 0..width {
+        for y in 0..height {
+            let mut count = 0;
+            for dx in -1i64..=1 {
+                for dy in -1i64..=1 {
+                    let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                    if (dx, dy) != (0, 0)
+                        && (0..width as i64).contains(&nx)
+                        && (0..height as i64).contains(&ny)
+                        && alive[nx as usize][ny as usize]
+                    {
+                        count += 1;
+                    }
+                }
+            }
+            next[x][y] = count == 3 || (alive[x][y] && count == 2);
+        }
+    }
+
+    next
+}
+
+fn alive_cells(cells: &Cells, width: usize, height: usize) -> Vec<Vec<bool>> {
+    (0..width)
+        .map(|x| {
+            (0..height)
+                .map(|y| cells.rows[x].values[y].is_alive())
+                .collect()
+        })
+        .collect()
+}
+
+fn board_with(width: usize, height: usize, alive: &[(usize, usize)]) -> Cells {
+    let mut cells = core::init_cells(width, height, false);
+    for (x, y) in alive {
+        cells.rows[*x].values[*y] = core::ALIVE_CELL;
+    }
+    cells
+}
+
+fn alive_set(cells: &Cells, width: usize, height: usize) -> HashSet<(usize, usize)> {
+    let mut alive = HashSet::new();
+    for x in 0..width {
+        for y in 0..height {
+            if cells.rows[x].values[y].is_alive() {
+                alive.insert((x, y));
+            }
+        }
+    }
+    alive
+}
+
+fn step(cells: &Cells, width: usize, height: usize, generations: usize) -> Cells {
+    let rule = Rule::default();
+    let mut cells = cells.clone();
+    for _ in 0..generations {
+        cells = core::step_cells(&cells, width, height, &rule);
+    }
+    cells
+}
+
+#[test]
+fn every_cell_has_the_cells_around_it_as_neighbours() {
+    for (width, height) in SIZES {
+        for x in 0..width {
+            for y in 0..height {
+                let mut neighbours: Vec<CellIndex> =
+                    core::neighbours(x, y, width, height).collect();
+                neighbours.sort();
+                assert_eq!(
+                    neighbours,
+                    reference_neighbours(x, y, width, height),
+                    "cell {x},{y} of a {width}x{height} board"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn corners_have_3_neighbours_edges_5_and_the_rest_8() {
+    let (width, height) = (7, 5);
+    for x in 0..width {
+        for y in 0..height {
+            let on_edge_x = x == 0 || x == width - 1;
+            let on_edge_y = y == 0 || y == height - 1;
+            let expected = match (on_edge_x, on_edge_y) {
+                (true, true) => 3,
+                (true, false) | (false, true) => 5,
+                (false, false) => 8,
+            };
+            assert_eq!(
+                core::neighbours(x, y, width, height).count(),
+                expected,
+                "cell {x},{y}"
+            );
+        }
+    }
+
+    // Along one row (or column) there's only left and right
+    assert_eq!(core::neighbours(0, 0, 1, 1).count(), 0);
+    assert_eq!(core::neighbours(0, 3, 1, 6).count(), 2);
+    assert_eq!(core::neighbours(5, 0, 6, 1).count(), 1);
+}
+
+#[test]
+fn the_indices_of_a_board_match_its_size() {
+    let (width, height) = (16, 9);
+    let cells = core::init_cells(width, height, false);
+
+    for x in 0..width {
+        for y in 0..height {
+            let from_cells = core::get_neighbours_indices(x, y, &cells);
+            let from_size: Vec<CellIndex> = core::neighbours(x, y, width, height).collect();
+            assert_eq!(from_cells, from_size, "cell {x},{y}");
+        }
+    }
+}
+
+#[test]
+fn alive_neighbours_are_counted_on_every_edge() {
+    for (width, height) in SIZES {
+        let cells = core::init_cells(width, height, true);
+        let alive = alive_cells(&cells, width, height);
+
+        for x in 0..width {
+            for y in 0..height {
+                let expected = reference_neighbours(x, y, width, height)
+                    .iter()
+                    .filter(|n| alive[n.x][n.y])
+                    .count();
+                assert_eq!(
+                    core::alive_neighbours(&cells, x, y, width, height),
+                    expected,
+                    "cell {x},{y} of a {width}x{height} board"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn random_soups_step_like_the_reference() {
+    for (width, height) in SIZES.into_iter().chain([(40, 23), (23, 40)]) {
+        let mut cells = core::init_cells(width, height, true);
+        let mut expected = alive_cells(&cells, width, height);
+
+        for generation in 1..=60 {
+            cells = step(&cells, width, height, 1);
+            expected = reference_step(&expected);
+            assert_eq!(
+                alive_cells(&cells, width, height),
+                expected,
+                "generation {generation} on a {width}x{height} board"
+            );
+        }
+    }
+}
+
+#[test]
+fn counting_all_at_once_agrees_with_one_cell_at_a_time() {
+    // The same rule as Life, written so it counts the whole board at once
+    let larger_than_life = Rule::parse("R1,C0,M0,S2..3,B3..3,NM").unwrap();
+    let (width, height) = (31, 17);
+    let mut life = core::init_cells(width, height, true);
+    let mut ltl = life.clone();
+
+    for generation in 1..=60 {
+        life = core::step_cells(&life, width, height, &Rule::default());
+        ltl = core::step_cells(&ltl, width, height, &larger_than_life);
+        assert_eq!(
+            alive_cells(&life, width, height),
+            alive_cells(&ltl, width, height),
+            "generation {generation}"
+        );
+    }
+}
+
+#[test]
+fn still_lifes_stay_put_in_every_corner() {
+    let (width, height) = (9, 7);
+    let (right, bottom) = (width - 2, height - 2);
+
+    for (x, y) in [(0, 0), (right, 0), (0, bottom), (right, bottom)] {
+        let block = [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)];
+        let cells = board_with(width, height, &block);
+        let after = step(&cells, width, height, 10);
+        assert_eq!(
+            alive_set(&after, width, height),
+            block.into_iter().collect(),
+            "block at {x},{y}"
+        );
+    }
+}
+
+#[test]
+fn blinkers_blink_along_every_edge() {
+    let (width, height) = (9, 7);
+
+    // Lying along the edge, one cell away from it
+    let blinkers = [
+        [(3, 1), (4, 1), (5, 1)],
+        [(3, height - 2), (4, height - 2), (5, height - 2)],
+        [(1, 2), (1, 3), (1, 4)],
+        [(width - 2, 2), (width - 2, 3), (width - 2, 4)],
+    ];
+
+    for blinker in blinkers {
+        let cells = board_with(width, height, &blinker);
+        let start = alive_set(&cells, width, height);
+
+        let once = step(&cells, width, height, 1);
+        assert_ne!(alive_set(&once, width, height), start, "{blinker:?}");
+        assert_eq!(alive_set(&once, width, height).len(), 3, "{blinker:?}");
+
+        for generations in [2, 10, 100] {
+            let after = step(&cells, width, height, generations);
+            assert_eq!(
+                alive_set(&after, width, height),
+                start,
+                "{blinker:?} after {generations}"
+            );
+        }
+    }
+}
+
+#[test]
+fn gliders_cross_the_board_in_every_direction() {
+    let (width, height) = (30, 24);
+    // Heading down and right, from the top-left corner
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    for (flip_x, flip_y) in [(false, false), (true, false), (false, true), (true, true)] {
+        let place = |(x, y): (usize, usize), shift: usize| {
+            let x = if flip_x {
+                width - 1 - x - shift
+            } else {
+                x + shift
+            };
+            let y = if flip_y {
+                height - 1 - y - shift
+            } else {
+                y + shift
+            };
+            (x, y)
+        };
+        let cells = board_with(width, height, &glider.map(|c| place(c, 0)));
+
+        // One cell diagonally every 4 generations, all the way to the far edge
+        let mut after = cells;
+        for shift in 1..=height - 3 {
+            after = step(&after, width, height, 4);
+            let expected: HashSet<(usize, usize)> =
+                glider.iter().map(|c| place(*c, shift)).collect();
+            assert_eq!(
+                alive_set(&after, width, height),
+                expected,
+                "glider flipped {flip_x},{flip_y} after {} generations",
+                shift * 4
+            );
+        }
+    }
+}
+
+#[test]
+fn triangles_have_12_neighbours_sharing_an_edge_or_a_corner() {
+    let triangular = Neighbourhood::triangular();
+    let (width, height) = (10, 6);
+    let layout = Layout::new(Geometry::Triangular, 640.0, 480.0, width, height, 4);
+    let corners = |x: i32, y: i32| -> Vec<(i64, i64)> {
+        layout
+            .polygon(x as usize, y as usize)
+            .iter()
+            .map(|p| ((p.x * 100.0).round() as i64, (p.y * 100.0).round() as i64))
+            .collect()
+    };
+
+    // (2, 2) points up, (3, 2) down
+    for (x, y) in [(2, 2), (3, 2)] {
+        let offsets = triangular.offsets_at(x as usize, y as usize);
+        assert_eq!(offsets.len(), 12, "{x},{y}");
+        assert_eq!(offsets.iter().collect::<HashSet<_>>().len(), 12);
+
+        // Exactly the triangles touching this one, on a board big enough for all of them
+        let touching: HashSet<(i32, i32)> = (0..width as i32)
+            .flat_map(|nx| (0..height as i32).map(move |ny| (nx, ny)))
+            .filter(|(nx, ny)| (*nx, *ny) != (x, y))
+            .filter(|(nx, ny)| corners(*nx, *ny).iter().any(|c| corners(x, y).contains(c)))
+            .map(|(nx, ny)| (nx - x, ny - y))
+            .collect();
+        assert_eq!(
+            offsets.into_iter().collect::<HashSet<_>>(),
+            touching,
+            "{x},{y}"
+        );
+    }
+}
+
+#[test]
+fn neighbours_count_each_other() {
+    for neighbourhood in [
+        Neighbourhood::triangular(),
+        Neighbourhood::hexagonal(1),
+        Neighbourhood::hexagonal(2),
+    ] {
+        for x in 0..4 {
+            for y in 0..4 {
+                for (dx, dy) in neighbourhood.offsets_at(x, y) {
+                    let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                    // Far enough from 0 that the other cell is on the board too
+                    let back = neighbourhood.offsets_at((nx + 8) as usize, (ny + 8) as usize);
+                    assert!(
+                        back.contains(&(-dx, -dy)),
+                        "{:?} at {x},{y} counts {nx},{ny} but not the other way round",
+                        neighbourhood.shape
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use game_of_life::core::{self, Cells};
+use game_of_life::rules::Rule;
+
+const SIZE: usize = 100;
+const GENERATIONS: usize = 20;
+
+// Every cell has the same state and history on both boards
+fn assert_same_cells(a: &Cells, b: &Cells, rule: &str, threads: usize) {
+    for x in 0..SIZE {
+        for y in 0..SIZE {
+            let (a, b) = (a.rows[x].values[y], b.rows[x].values[y]);
+            assert_eq!(
+                (a.state, a.age, a.time_since_death, a.activity),
+                (b.state, b.age, b.time_since_death, b.activity),
+                "cell {x},{y} differs with {rule} on {threads} threads"
+            );
+        }
+    }
+}
+
+fn assert_parallel_matches(rulestring: &str) {
+    let rule = Rule::parse(rulestring).unwrap();
+    let start = core::init_cells(SIZE, SIZE, true);
+
+    let mut single = start.clone();
+    for _ in 0..GENERATIONS {
+        single = core::step_cells(&single, SIZE, SIZE, &rule);
+    }
+
+    // Including more threads than rows, and bands that don't divide the board evenly
+    for threads in [2, 3, 4, 7, 16, SIZE + 1] {
+        let mut parallel = start.clone();
+        for _ in 0..GENERATIONS {
+            parallel = core::step_cells_in_parallel(&parallel, SIZE, SIZE, &rule, threads);
+        }
+        assert_same_cells(&single, &parallel, rulestring, threads);
+    }
+}
+
+#[test]
+fn life_is_the_same_on_any_number_of_threads() {
+    assert_parallel_matches("life");
+}
+
+#[test]
+fn generations_rules_are_the_same_on_any_number_of_threads() {
+    assert_parallel_matches("brians-brain");
+}
+
+#[test]
+fn larger_than_life_is_the_same_on_any_number_of_threads() {
+    assert_parallel_matches("bosco");
+}
+
+#[test]
+fn non_totalistic_rules_are_the_same_on_any_number_of_threads() {
+    assert_parallel_matches("tlife");
+}
+
+#[test]
+fn hexagonal_rules_are_the_same_on_any_number_of_threads() {
+    assert_parallel_matches("hex-life");
+}
+
+#[test]
+fn rule_tables_are_the_same_on_any_number_of_threads() {
+    assert_parallel_matches("wireworld");
+}
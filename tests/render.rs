@@ -0,0 +1,176 @@
+use nannou::prelude::*;
+
+use game_of_life::core::{self, Cells, RenderMode};
+use game_of_life::geometry::{Geometry, Layout};
+use game_of_life::plane::{Camera, Plane};
+use game_of_life::render::{self, Image};
+use game_of_life::rules::{self, Rule};
+use game_of_life::theme::{self, Theme};
+
+const SIZE: usize = 16;
+const WINDOW: f32 = 256.0;
+
+// A glider and a blinker, away from each other
+fn board() -> Cells {
+    let mut cells = core::init_cells(SIZE, SIZE, false);
+    for (x, y) in [
+        (1, 0),
+        (2, 1),
+        (0, 2),
+        (1, 2),
+        (2, 2),
+        (10, 10),
+        (11, 10),
+        (12, 10),
+    ] {
+        cells.rows[x].values[y] = core::ALIVE_CELL;
+    }
+    cells
+}
+
+fn draw_board(cells: &Cells, layout: &Layout, theme: &Theme) -> Image {
+    let triangles = render::board_mesh(cells, layout, RenderMode::Alive, theme, &Rule::default());
+    render::rasterise(
+        &triangles,
+        theme.background,
+        WINDOW as usize,
+        WINDOW as usize,
+    )
+}
+
+// The pixel under a point of the window
+fn pixel_at(image: &Image, point: Point2) -> Rgb8 {
+    let x = (point.x + image.width as f32 * 0.5).floor() as usize;
+    let y = (image.height as f32 * 0.5 - point.y).floor() as usize;
+    image.pixel(x, y)
+}
+
+// The middle of every cell is drawn in its colour
+fn assert_cells_drawn(geometry: Geometry) {
+    let layout = Layout::new(geometry, WINDOW, WINDOW, SIZE, SIZE, WINDOW as usize / SIZE);
+    let cells = board();
+    let theme = theme::CLASSIC;
+    let image = draw_board(&cells, &layout, &theme);
+
+    for x in 0..SIZE {
+        for y in 0..SIZE {
+            let expected = if cells.rows[x].values[y].is_alive() {
+                theme.alive
+            } else {
+                theme.background
+            };
+            assert_eq!(
+                pixel_at(&image, layout.centre(x as f32, y as f32)),
+                expected,
+                "cell {x},{y} on the {geometry} board"
+            );
+        }
+    }
+}
+
+#[test]
+fn square_cells_fill_their_whole_square() {
+    let cell_size = WINDOW as usize / SIZE;
+    let layout = Layout::new(Geometry::Square, WINDOW, WINDOW, SIZE, SIZE, cell_size);
+    let cells = board();
+    let theme = theme::CLASSIC;
+    let image = draw_board(&cells, &layout, &theme);
+
+    for px in 0..image.width {
+        for py in 0..image.height {
+            let cell = cells.rows[px / cell_size].values[py / cell_size];
+            let expected = if cell.is_alive() {
+                theme.alive
+            } else {
+                theme.background
+            };
+            assert_eq!(image.pixel(px, py), expected, "pixel {px},{py}");
+        }
+    }
+}
+
+#[test]
+fn hexagons_are_drawn_where_the_cells_are() {
+    assert_cells_drawn(Geometry::Hexagonal);
+}
+
+#[test]
+fn triangles_are_drawn_where_the_cells_are() {
+    assert_cells_drawn(Geometry::Triangular);
+}
+
+#[test]
+fn only_cells_that_stand_out_from_the_background_are_in_the_mesh() {
+    let layout = Layout::new(Geometry::Square, WINDOW, WINDOW, SIZE, SIZE, 16);
+    let rule = Rule::default();
+
+    let empty = core::init_cells(SIZE, SIZE, false);
+    let triangles = render::board_mesh(&empty, &layout, RenderMode::Alive, &theme::CLASSIC, &rule);
+    assert!(triangles.is_empty());
+
+    // Two triangles per alive square
+    let triangles =
+        render::board_mesh(&board(), &layout, RenderMode::Alive, &theme::CLASSIC, &rule);
+    assert_eq!(triangles.len(), 2 * 8);
+
+    // Unless the dead cells have a colour of their own
+    let theme = Theme {
+        dead: rgb8(40, 40, 40),
+        ..theme::CLASSIC
+    };
+    let triangles = render::board_mesh(&board(), &layout, RenderMode::Alive, &theme, &rule);
+    assert_eq!(triangles.len(), 2 * SIZE * SIZE);
+    let image = draw_board(&board(), &layout, &theme);
+    assert_eq!(pixel_at(&image, layout.centre(5.0, 5.0)), theme.dead);
+}
+
+#[test]
+fn dying_cells_are_drawn_in_their_own_colour() {
+    let layout = Layout::new(Geometry::Square, WINDOW, WINDOW, SIZE, SIZE, 16);
+    let rule = Rule::parse("brians-brain").unwrap();
+    let theme = theme::CLASSIC;
+
+    let mut cells = core::init_cells(SIZE, SIZE, false);
+    cells.rows[3].values[4] = core::next_cell(core::ALIVE_CELL, rules::ALIVE + 1);
+
+    let triangles = render::board_mesh(&cells, &layout, RenderMode::Alive, &theme, &rule);
+    let image = render::rasterise(&triangles, theme.background, 256, 256);
+    let expected = core::cell_color(&cells.rows[3].values[4], RenderMode::Alive, &theme, &rule);
+    assert_ne!(expected, theme.background);
+    assert_eq!(pixel_at(&image, layout.centre(3.0, 4.0)), expected);
+}
+
+#[test]
+fn the_plane_is_drawn_through_the_camera() {
+    let theme = theme::CLASSIC;
+    let rule = Rule::default();
+    let mut plane = Plane::new();
+    plane.set(0, 0, rules::ALIVE);
+    plane.set(-3, 2, rules::ALIVE);
+    // Far out of view
+    plane.set(1000, 1000, rules::ALIVE);
+
+    let mut camera = Camera::new(8.0);
+    camera.x = 0.5;
+    camera.y = 0.5;
+
+    let triangles = render::plane_mesh(
+        &plane,
+        &camera,
+        WINDOW,
+        WINDOW,
+        RenderMode::Alive,
+        &theme,
+        &rule,
+    );
+    assert_eq!(triangles.len(), 2 * 2);
+
+    // 0,0 is right in the middle of the window, and every cell is 8 pixels across
+    let image = render::rasterise(&triangles, theme.background, 256, 256);
+    for (x, y) in [(124, 124), (131, 131), (100, 140), (107, 147)] {
+        assert_eq!(image.pixel(x, y), theme.alive, "pixel {x},{y}");
+    }
+    for (x, y) in [(123, 124), (132, 131), (128, 140), (99, 140)] {
+        assert_eq!(image.pixel(x, y), theme.background, "pixel {x},{y}");
+    }
+}
@@ -0,0 +1,137 @@
+use game_of_life::rle::{self, Pattern};
+use game_of_life::rules::Rule;
+
+fn pattern(width: usize, height: usize, rule: &str, cells: &[(i32, i32, u8)]) -> Pattern {
+    Pattern {
+        width,
+        height,
+        rule: Some(Rule::parse(rule).unwrap()),
+        cells: cells.to_vec(),
+        position: None,
+    }
+}
+
+#[test]
+fn every_state_has_its_letters() {
+    let letters = [
+        (0, "."),
+        (1, "A"),
+        (2, "B"),
+        (24, "X"),
+        (25, "pA"),
+        (48, "pX"),
+        (49, "qA"),
+        (241, "yA"),
+        (255, "yO"),
+    ];
+    for (state, expected) in letters {
+        assert_eq!(rle::state_to_letters(state), expected, "state {state}");
+    }
+
+    // And back again, with a run of a prefixed state
+    let read = rle::parse_rle("x = 7, y = 1\n.AX2pAqAyO!").unwrap();
+    assert_eq!(
+        read.cells,
+        [
+            (1, 0, 1),
+            (2, 0, 24),
+            (3, 0, 25),
+            (4, 0, 25),
+            (5, 0, 49),
+            (6, 0, 255)
+        ]
+    );
+
+    // 'yP' would be 256
+    assert!(rle::parse_rle("x = 1, y = 1\nyP!").is_err());
+}
+
+#[test]
+fn the_position_comes_from_the_cxrle_line() {
+    let text = "#CXRLE Pos=-10,-5 Gen=3\nx = 3, y = 1\n3o!";
+    let read = rle::parse_rle(text).unwrap();
+    assert_eq!(read.position, Some((-10, -5)));
+    assert_eq!(read.cells, [(0, 0, 1), (1, 0, 1), (2, 0, 1)]);
+
+    // Written back the same way, before the header
+    let written = rle::write_rle(&read).unwrap();
+    assert!(
+        written.starts_with("#CXRLE Pos=-10,-5\nx = 3, y = 1"),
+        "{written}"
+    );
+    assert_eq!(rle::parse_rle(&written).unwrap(), read);
+
+    // No position without one
+    let read = rle::parse_rle("#CXRLE Gen=3\nx = 1, y = 1\no!").unwrap();
+    assert_eq!(read.position, None);
+
+    assert!(rle::parse_rle("#CXRLE Pos=3\nx = 1, y = 1\no!").is_err());
+    assert!(rle::parse_rle("#CXRLE Pos=a,b\nx = 1, y = 1\no!").is_err());
+}
+
+#[test]
+fn rules_can_have_commas_in_the_header() {
+    let rulestring = "R2,C0,M1,S3..5,B4..6,NN";
+    let text = format!("x = 3, y = 3, rule = {rulestring}\nbo$2bo$3o!");
+    let read = rle::parse_rle(&text).unwrap();
+
+    assert_eq!((read.width, read.height), (3, 3));
+    assert_eq!(read.rule, Some(Rule::parse(rulestring).unwrap()));
+    assert_eq!(read.cells.len(), 5);
+
+    let written = rle::write_rle(&read).unwrap();
+    assert_eq!(rle::parse_rle(&written).unwrap(), read);
+}
+
+#[test]
+fn long_lines_are_wrapped_at_70_characters() {
+    // Runs of 12 cells alive and 12 dead, every run is written as 3 characters
+    let cells: Vec<(i32, i32, u8)> = (0..600)
+        .filter(|x| x % 24 < 12)
+        .map(|x| (x, 0, 1))
+        .collect();
+    let long = pattern(588, 1, "life", &cells);
+
+    let written = rle::write_rle(&long).unwrap();
+    let body: Vec<&str> = written.lines().skip(1).collect();
+
+    assert!(body.len() > 1, "{written}");
+    for line in &body {
+        assert!(line.len() <= rle::MAX_LINE_LENGTH, "{line}");
+    }
+    // The run that would go past 70 goes on the next line instead of being cut
+    assert_eq!(body[0].len(), 69);
+    assert!(body[1].starts_with("12"), "{}", body[1]);
+
+    assert_eq!(rle::parse_rle(&written).unwrap(), long);
+}
+
+#[test]
+fn generations_patterns_are_written_the_way_they_are_read() {
+    // A Brian's Brain cell and its dying tail
+    let brain = pattern(
+        4,
+        2,
+        "brians-brain",
+        &[(0, 0, 2), (1, 0, 1), (2, 0, 1), (1, 1, 2), (3, 1, 2)],
+    );
+
+    let written = rle::write_rle(&brain).unwrap();
+    assert_eq!(written, "x = 4, y = 2, rule = B2/S/C3\nB2A$.B.B!\n");
+    assert_eq!(rle::parse_rle(&written).unwrap(), brain);
+
+    // Living cells only, still written in letters, the rule has more than 2 states
+    let alive = pattern(2, 1, "brians-brain", &[(0, 0, 1), (1, 0, 1)]);
+    let written = rle::write_rle(&alive).unwrap();
+    assert_eq!(written, "x = 2, y = 1, rule = B2/S/C3\n2A!\n");
+    assert_eq!(rle::parse_rle(&written).unwrap(), alive);
+}
+
+#[test]
+fn two_state_patterns_are_written_in_b_and_o() {
+    let glider = rle::parse_rle("x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!").unwrap();
+    let written = rle::write_rle(&glider).unwrap();
+
+    assert_eq!(written, "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+    assert_eq!(rle::parse_rle(&written).unwrap(), glider);
+}
@@ -0,0 +1,96 @@
+use game_of_life::neighbourhood::{Neighbourhood, Shape};
+use game_of_life::rle::{self, Pattern};
+use game_of_life::rules::{Rule, NAMED_RULES};
+
+// Written back the way they're read, and read back as the same rule
+const RULESTRINGS: [&str; 14] = [
+    "B3/S23",
+    "B/S012345678",
+    "B0/S8/C5",
+    "B2/S34H",
+    "B2/S013V",
+    "B4/S3,4,5T",
+    "B2n3/S23-q",
+    "B2ce3eaiy/S1c2-a/C7",
+    "W110",
+    "R5,C0,M1,S34..58,B34..45,NM",
+    "R2,C3,M0,S2..3,5,7..9,B3,11,NN",
+    "R3,C0,M1,S,B4..6,20..25,NH",
+    "R1,C0,M1,S0,2,4,6,8,B1,3,5,7,9,NT",
+    "WireWorld",
+];
+
+#[test]
+fn rules_are_written_the_way_they_are_read() {
+    for rulestring in RULESTRINGS {
+        let rule = Rule::parse(rulestring).unwrap();
+        let written = rule.to_rulestring().unwrap();
+        assert_eq!(written, rulestring);
+        assert_eq!(Rule::parse(&written).unwrap(), rule, "{rulestring}");
+    }
+}
+
+#[test]
+fn named_rules_survive_being_written() {
+    for (name, rulestring) in NAMED_RULES {
+        let rule = Rule::parse(name).unwrap();
+        let written = rule.to_rulestring().unwrap();
+        assert_eq!(written, rulestring, "{name}");
+        assert_eq!(Rule::parse(&written).unwrap(), rule, "{name}");
+    }
+}
+
+#[test]
+fn larger_than_life_counts_keep_their_gaps() {
+    // Other ways of writing the same rule
+    let rule = Rule::parse("R2,C0,M0,S2..3,5,B3,NM").unwrap();
+    let same = Rule::parse("r2,c0,m0,s5,2,3,b3..3,nm").unwrap();
+    assert_eq!(rule, same);
+
+    let survival: Vec<usize> = (0..rule.survival.len())
+        .filter(|n| rule.survival[*n])
+        .collect();
+    assert_eq!(survival, [2, 3, 5]);
+    assert_eq!(rule.to_string(), "R2,C0,M0,S2..3,5,B3,NM");
+
+    // Numbers have to come after S or B
+    for bad in [
+        "R2,3,C0,M0,S2,B3,NM",
+        "R2,C0,M0,S2,B3,NM,4",
+        "R2,C0,M0,S2..,B3,NM",
+    ] {
+        assert!(Rule::parse(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn custom_neighbourhoods_are_not_written_down() {
+    let knight = Neighbourhood {
+        shape: Shape::Custom(vec![
+            (-2, -1),
+            (-1, -2),
+            (1, -2),
+            (2, -1),
+            (2, 1),
+            (1, 2),
+            (-1, 2),
+            (-2, 1),
+        ]),
+        radius: 2,
+    };
+    let rule = Rule::parse("B3/S23").unwrap().with_neighbourhood(knight);
+
+    assert!(rule.to_rulestring().is_err());
+    // Still something to show on screen
+    assert_eq!(rule.to_string(), "S2..3,B3 on a custom neighbourhood");
+
+    // Saving it would give a file with some other rule in it
+    let pattern = Pattern {
+        width: 1,
+        height: 1,
+        rule: Some(rule),
+        cells: vec![(0, 0, 1)],
+        position: None,
+    };
+    assert!(rle::write_rle(&pattern).is_err());
+}
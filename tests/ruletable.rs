@@ -0,0 +1,217 @@
+use game_of_life::core::{self, Cells};
+use game_of_life::rle;
+use game_of_life::rules::Rule;
+use game_of_life::ruletable::{self, RuleTable};
+
+// Electron heads are A, tails B and conductors C (empty is '.')
+const WIRE: &str = "x = 8, y = 1, rule = WireWorld\nBA6C!";
+// A loop of 16 cells with an electron going round, and a wire off its right side
+const CLOCK: &str = "x = 12, y = 4, rule = WireWorld\nCBA3C$C4.C$C4.6C$6C!";
+
+// Neighbours go clockwise from the north one, the same as in .rule files
+const N: usize = 0;
+const NE: usize = 1;
+const E: usize = 2;
+const SE: usize = 3;
+const S: usize = 4;
+const SW: usize = 5;
+const W: usize = 6;
+const NW: usize = 7;
+
+fn table(symmetries: &str, transitions: &str) -> RuleTable {
+    let text = format!(
+        "@RULE Test\n@TABLE\nn_states:3\nneighborhood:Moore\nsymmetries:{symmetries}\n{transitions}\n"
+    );
+    ruletable::parse_rule_table(&text).unwrap()
+}
+
+// The neighbours in the given states, the rest dead
+fn neighbours(alive: &[(usize, u8)]) -> [u8; 8] {
+    let mut neighbours = [0; 8];
+    for (index, state) in alive {
+        neighbours[*index] = *state;
+    }
+    neighbours
+}
+
+fn board(rle: &str, width: usize, height: usize) -> Cells {
+    let pattern = rle::parse_rle(rle).unwrap();
+    let mut cells = core::init_cells(width, height, false);
+    rle::place_pattern(&pattern, &mut cells, width, height, 1, 1);
+    cells
+}
+
+fn states_where(cells: &Cells, state: u8) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    for (x, row) in cells.rows.iter().enumerate() {
+        for (y, cell) in row.values.iter().enumerate() {
+            if cell.state == state {
+                found.push((x, y));
+            }
+        }
+    }
+    found
+}
+
+#[test]
+fn electrons_run_along_a_wire() {
+    let rule = Rule::parse("wireworld").unwrap();
+    let (width, height) = (10, 3);
+    let mut cells = board(WIRE, width, height);
+
+    for generation in 1..=6 {
+        cells = core::step_cells(&cells, width, height, &rule);
+        // The head moves one cell to the right, with its tail right behind
+        assert_eq!(states_where(&cells, 1), [(2 + generation, 1)]);
+        assert_eq!(states_where(&cells, 2), [(1 + generation, 1)]);
+        assert_eq!(states_where(&cells, 3).len(), 6);
+    }
+
+    // Then runs out of wire
+    for _ in 0..2 {
+        cells = core::step_cells(&cells, width, height, &rule);
+    }
+    assert!(states_where(&cells, 1).is_empty());
+    assert_eq!(states_where(&cells, 3).len(), 8);
+}
+
+#[test]
+fn a_clock_sends_an_electron_down_the_wire_every_12_generations() {
+    let rule = Rule::parse("wireworld").unwrap();
+    let (width, height) = (14, 6);
+    let mut cells = board(CLOCK, width, height);
+    let conductors = states_where(&cells, 3).len() + 2;
+    // The loop, and the last cell of the wire
+    let in_the_loop = |(x, y): &(usize, usize)| *x <= 6 && *y <= 4;
+    let end = (11, 3);
+
+    // The electron cuts across the corners, so it takes 12 generations to
+    // go round the 16 cells, and the first time round it's still settling
+    for _ in 0..12 {
+        cells = core::step_cells(&cells, width, height, &rule);
+    }
+    let lap = states_where(&cells, 1);
+    let mut heads_at_the_end = Vec::new();
+
+    for generation in 1..=48 {
+        cells = core::step_cells(&cells, width, height, &rule);
+        assert_eq!(
+            states_where(&cells, 0).len() + conductors,
+            width * height,
+            "nothing appears or goes away (generation {generation})"
+        );
+        if generation % 12 == 0 {
+            let heads = states_where(&cells, 1);
+            let around: Vec<_> = heads.iter().filter(|head| in_the_loop(head)).collect();
+            assert_eq!(
+                around,
+                lap.iter()
+                    .filter(|head| in_the_loop(head))
+                    .collect::<Vec<_>>()
+            );
+        }
+        if cells.rows[end.0].values[end.1].state == 1 {
+            heads_at_the_end.push(generation);
+        }
+    }
+
+    let gaps: Vec<i32> = heads_at_the_end.windows(2).map(|w| w[1] - w[0]).collect();
+    assert_eq!(heads_at_the_end.len(), 4, "{heads_at_the_end:?}");
+    assert!(gaps.iter().all(|gap| *gap == 12), "{heads_at_the_end:?}");
+}
+
+#[test]
+fn rotations_and_reflections_are_expanded() {
+    // Born next to a north and north-east neighbour in state 1
+    let transition = "0,1,1,0,0,0,0,0,0,2";
+    let rotate4 = table("rotate4", transition);
+    let rotate4reflect = table("rotate4reflect", transition);
+    let none = table("none", transition);
+
+    let rotated = [(N, NE), (E, SE), (S, SW), (W, NW)];
+    let reflected = [(N, NW), (E, NE), (S, SE), (W, SW)];
+
+    for (a, b) in rotated {
+        let around = neighbours(&[(a, 1), (b, 1)]);
+        assert_eq!(rotate4.next_state(0, &around), 2, "{a},{b}");
+        assert_eq!(rotate4reflect.next_state(0, &around), 2, "{a},{b}");
+    }
+    for (a, b) in reflected {
+        let around = neighbours(&[(a, 1), (b, 1)]);
+        assert_eq!(rotate4.next_state(0, &around), 0, "{a},{b}");
+        assert_eq!(rotate4reflect.next_state(0, &around), 2, "{a},{b}");
+    }
+
+    assert_eq!(none.next_state(0, &neighbours(&[(N, 1), (NE, 1)])), 2);
+    assert_eq!(none.next_state(0, &neighbours(&[(E, 1), (SE, 1)])), 0);
+    // Not next to each other, so not the same in any symmetry
+    let apart = neighbours(&[(N, 1), (S, 1)]);
+    assert_eq!(rotate4reflect.next_state(0, &apart), 0);
+}
+
+#[test]
+fn permute_only_counts_the_neighbours() {
+    let permute = table("permute", "0,1,1,0,0,0,0,0,0,2");
+
+    for (a, b) in [(N, NE), (N, S), (SW, E), (W, NW)] {
+        let around = neighbours(&[(a, 1), (b, 1)]);
+        assert_eq!(permute.next_state(0, &around), 2, "{a},{b}");
+    }
+    assert_eq!(permute.next_state(0, &neighbours(&[(N, 1)])), 0);
+    assert_eq!(
+        permute.next_state(0, &neighbours(&[(N, 1), (S, 1), (E, 1)])),
+        0
+    );
+    assert_eq!(permute.next_state(0, &neighbours(&[(N, 1), (S, 2)])), 0);
+}
+
+#[test]
+fn variables_are_bound_to_the_first_state_they_match() {
+    // Born in the state of two equal neighbours to the north and north-east
+    let bound = table("none", "var a={1,2}\n0,a,a,0,0,0,0,0,0,a");
+
+    assert_eq!(bound.next_state(0, &neighbours(&[(N, 1), (NE, 1)])), 1);
+    assert_eq!(bound.next_state(0, &neighbours(&[(N, 2), (NE, 2)])), 2);
+    assert_eq!(bound.next_state(0, &neighbours(&[(N, 1), (NE, 2)])), 0);
+    assert_eq!(bound.next_state(0, &neighbours(&[(N, 2), (NE, 1)])), 0);
+
+    // Two variables for the same states don't have to match
+    let unbound = table("none", "var a={1,2}\nvar b={1,2}\n0,a,b,0,0,0,0,0,0,b");
+    assert_eq!(unbound.next_state(0, &neighbours(&[(N, 1), (NE, 2)])), 2);
+}
+
+#[test]
+fn colours_are_read_per_state_or_as_a_gradient() {
+    let wireworld = ruletable::parse_rule_table(ruletable::WIREWORLD).unwrap();
+    assert_eq!(
+        wireworld.colors,
+        [
+            Some([48, 48, 48]),
+            Some([0, 128, 255]),
+            Some([255, 255, 255]),
+            Some([255, 128, 0]),
+        ]
+    );
+
+    let gradient =
+        "@RULE Fade\n@TABLE\nn_states:4\n1,0,0,0,0,0,0,0,0,2\n@COLORS\n255 0 0 0 0 255\n";
+    let fade = ruletable::parse_rule_table(gradient).unwrap();
+    assert_eq!(
+        fade.colors,
+        [
+            None,
+            Some([255, 0, 0]),
+            Some([128, 0, 128]),
+            Some([0, 0, 255])
+        ]
+    );
+
+    // Colours for states that don't exist are ignored, broken ones aren't
+    let text = "@RULE Odd\n@TABLE\nn_states:2\n1,0,0,0,0,0,0,0,0,0\n@COLORS\n5 1 2 3\n";
+    assert_eq!(
+        ruletable::parse_rule_table(text).unwrap().colors,
+        [None, None]
+    );
+    let text = "@RULE Odd\n@TABLE\nn_states:2\n1,0,0,0,0,0,0,0,0,0\n@COLORS\n1 300 2 3\n";
+    assert!(ruletable::parse_rule_table(text).is_err());
+}
@@ -0,0 +1,85 @@
+use std::fs;
+
+use game_of_life::core::{self, Cells};
+use game_of_life::rle;
+use game_of_life::rules::Rule;
+use game_of_life::stats::{self, BoundingBox, StatsHistory};
+
+const SIZE: usize = 5;
+
+// A blinker across the middle of the board, and the generation after it
+fn blinker() -> (Cells, Cells) {
+    let pattern = rle::parse_rle("x = 3, y = 1\n3o!").unwrap();
+    let (cells, _clipped) = rle::centred_pattern(&pattern, SIZE, SIZE);
+    let next = core::step_cells(&cells, SIZE, SIZE, &Rule::conway());
+    (cells, next)
+}
+
+#[test]
+fn births_and_deaths_are_counted_against_the_generation_before() {
+    let (first, second) = blinker();
+
+    // Nothing to compare the first generation with
+    let stats = stats::compute_stats(0, None, &first, SIZE, SIZE);
+    assert_eq!(stats.population, 3);
+    assert_eq!((stats.births, stats.deaths), (0, 0));
+    assert_eq!(
+        stats.bounding_box,
+        Some(BoundingBox {
+            min_x: 1,
+            min_y: 2,
+            max_x: 3,
+            max_y: 2
+        })
+    );
+
+    // The ends die and come back above and below the middle
+    let stats = stats::compute_stats(1, Some(&first), &second, SIZE, SIZE);
+    assert_eq!(stats.generation, 1);
+    assert_eq!(stats.population, 3);
+    assert_eq!((stats.births, stats.deaths), (2, 2));
+    let bounds = stats.bounding_box.unwrap();
+    assert_eq!(
+        (bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y),
+        (2, 1, 2, 3)
+    );
+    assert_eq!((bounds.width(), bounds.height()), (1, 3));
+    assert_eq!(stats.density, 3.0 / 25.0);
+    assert_eq!(stats::population(&second, SIZE, SIZE), 3);
+
+    // Everything dying leaves no bounding box
+    let empty = core::init_cells(SIZE, SIZE, false);
+    let stats = stats::compute_stats(2, Some(&second), &empty, SIZE, SIZE);
+    assert_eq!((stats.population, stats.births, stats.deaths), (0, 0, 3));
+    assert_eq!(stats.bounding_box, None);
+    assert_eq!(stats.density, 0.0);
+}
+
+#[test]
+fn history_is_exported_as_csv() {
+    let (first, second) = blinker();
+    let empty = core::init_cells(SIZE, SIZE, false);
+
+    let mut history = StatsHistory::new();
+    history.record(stats::compute_stats(0, None, &first, SIZE, SIZE));
+    history.record(stats::compute_stats(1, Some(&first), &second, SIZE, SIZE));
+    history.record(stats::compute_stats(2, Some(&second), &empty, SIZE, SIZE));
+
+    let expected = "generation,population,births,deaths,min_x,min_y,max_x,max_y,density\n\
+                    0,3,0,0,1,2,3,2,0.12\n\
+                    1,3,2,2,2,1,2,3,0.12\n\
+                    2,0,0,3,,,,,0\n";
+    assert_eq!(history.to_csv(), expected);
+    assert_eq!(history.recent_populations(2), [3, 0]);
+    assert_eq!(history.latest().unwrap().generation, 2);
+
+    let path = std::env::temp_dir().join(format!("game-of-life-{}-stats.csv", std::process::id()));
+    history.write_csv(&path).unwrap();
+    let written = fs::read_to_string(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(written.unwrap(), expected);
+
+    // Only the header once there's nothing recorded
+    history.clear();
+    assert_eq!(history.to_csv().lines().count(), 1);
+}
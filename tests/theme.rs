@@ -0,0 +1,154 @@
+use std::fs;
+
+use nannou::prelude::*;
+
+use game_of_life::core::{self, Cell, RenderMode};
+use game_of_life::error::Error;
+use game_of_life::rules::Rule;
+use game_of_life::theme::{self, Theme};
+
+fn rgb(red: u8, green: u8, blue: u8) -> Rgb8 {
+    Rgb8::new(red, green, blue)
+}
+
+#[test]
+fn every_preset_can_be_picked_by_name() {
+    for preset in theme::PRESETS {
+        assert_eq!(theme::preset(preset.name), Some(preset));
+        assert_eq!(theme::theme_from_arg(preset.name).unwrap(), preset);
+    }
+    assert_eq!(theme::preset("sepia"), None);
+    assert_eq!(Theme::default(), theme::CLASSIC);
+}
+
+#[test]
+fn presets_go_round_in_order() {
+    let mut theme = theme::CLASSIC;
+    let mut names = Vec::new();
+    for _ in 0..theme::PRESETS.len() {
+        names.push(theme.name);
+        theme = theme.next_preset();
+    }
+
+    assert_eq!(names, ["classic", "light", "high-contrast", "colour-blind"]);
+    assert_eq!(theme, theme::CLASSIC);
+
+    // From a theme file, back to the first one
+    let custom = theme::parse_theme("preset = \"light\"").unwrap();
+    assert_eq!(custom.next_preset(), theme::CLASSIC);
+}
+
+#[test]
+fn colours_are_exactly_hash_and_6_hex_digits() {
+    assert_eq!(theme::parse_hex_color("#ff8000").unwrap(), rgb(255, 128, 0));
+    assert_eq!(theme::parse_hex_color("#00A0fF").unwrap(), rgb(0, 160, 255));
+
+    for bad in [
+        "ff8000", "##ff8000", "#ff800", "#ff80000", "#+f8000", "#ff+f00", "#ff80 0", " #ff8000",
+        "#ff8000 ", "#gg8000", "#ff80é", "",
+    ] {
+        match theme::parse_hex_color(bad) {
+            Err(Error::Invalid(_)) => {}
+            other => panic!("expected '{bad}' to be invalid, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn theme_files_change_a_preset() {
+    let text = "preset = \"high-contrast\"\nalive = \"#ff0000\"\nhud_background = \"#102030\"\n";
+    let theme = theme::parse_theme(text).unwrap();
+
+    assert_eq!(theme.name, "custom");
+    assert_eq!(theme.alive, rgb(255, 0, 0));
+    assert_eq!(theme.hud_background, rgb(16, 32, 48));
+    // The rest is the preset's
+    assert_eq!(theme.grid, theme::HIGH_CONTRAST.grid);
+    assert_eq!(theme.selection, theme::HIGH_CONTRAST.selection);
+
+    // Without a preset, the classic theme
+    let theme = theme::parse_theme("grid = \"#333333\"").unwrap();
+    assert_eq!(theme.grid, rgb(51, 51, 51));
+    assert_eq!(theme.alive, theme::CLASSIC.alive);
+
+    // And from a file
+    let path = std::env::temp_dir().join(format!("game-of-life-{}-theme.toml", std::process::id()));
+    fs::write(&path, text).unwrap();
+    let loaded = theme::theme_from_arg(path.to_str().unwrap());
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap(), theme::parse_theme(text).unwrap());
+}
+
+#[test]
+fn theme_files_only_have_the_keys_we_know() {
+    // A typo would otherwise be silently ignored
+    let error = theme::parse_theme("preset = \"light\"\nalvie = \"#ff0000\"\n").unwrap_err();
+    assert!(error.to_string().contains("alvie"), "{error}");
+
+    assert!(theme::parse_theme("preset = \"sepia\"").is_err());
+    assert!(theme::parse_theme("alive = \"ff0000\"").is_err());
+    assert!(theme::parse_theme("alive = \"##ff0000\"").is_err());
+    assert!(theme::parse_theme("alive = 255").is_err());
+}
+
+#[test]
+fn every_mode_is_drawn_in_the_colours_of_the_theme() {
+    let rule = Rule::conway();
+    let born = core::fresh_cell(1);
+    let survived = core::next_cell(core::next_cell(born, 1), 1);
+    // The start of the fade, the generation after it's one step in
+    let died = Cell {
+        time_since_death: 0,
+        ..core::next_cell(survived, 0)
+    };
+    let color =
+        |cell: &Cell, mode: RenderMode, theme: &Theme| core::cell_color(cell, mode, theme, &rule);
+
+    for theme in theme::PRESETS {
+        assert_eq!(color(&born, RenderMode::BirthsDeaths, &theme), theme.born);
+        assert_eq!(
+            color(&survived, RenderMode::BirthsDeaths, &theme),
+            theme.survived
+        );
+        assert_eq!(color(&died, RenderMode::BirthsDeaths, &theme), theme.died);
+        // Age 1 is the start of the gradient
+        assert_eq!(
+            color(&core::next_cell(born, 1), RenderMode::Age, &theme),
+            theme.age[0]
+        );
+    }
+
+    // Told apart by something else than red and green
+    let (classic, colour_blind) = (theme::CLASSIC, theme::COLOUR_BLIND);
+    for cell in [born, died] {
+        assert_ne!(
+            color(&cell, RenderMode::BirthsDeaths, &classic),
+            color(&cell, RenderMode::BirthsDeaths, &colour_blind)
+        );
+    }
+    assert_ne!(classic.age, colour_blind.age);
+    assert_ne!(classic.activity, colour_blind.activity);
+}
+
+#[test]
+fn theme_files_change_the_gradients() {
+    let text = "preset = \"colour-blind\"\ndied = \"#ff00ff\"\nage = [\"#000001\", \"#000002\", \"#000003\", \"#000004\"]\n";
+    let theme = theme::parse_theme(text).unwrap();
+
+    assert_eq!(theme.died, rgb(255, 0, 255));
+    assert_eq!(
+        theme.age,
+        [rgb(0, 0, 1), rgb(0, 0, 2), rgb(0, 0, 3), rgb(0, 0, 4)]
+    );
+    assert_eq!(theme.born, theme::COLOUR_BLIND.born);
+    assert_eq!(theme.activity, theme::COLOUR_BLIND.activity);
+
+    // All 4 colours, or none
+    for bad in [
+        "age = [\"#000001\"]",
+        "activity = []",
+        "age = [\"#000001\", \"#000002\", \"#000003\", \"000004\"]",
+    ] {
+        assert!(theme::parse_theme(bad).is_err(), "{bad}");
+    }
+}
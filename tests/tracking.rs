@@ -0,0 +1,132 @@
+use game_of_life::core::{self, Cells};
+use game_of_life::rle;
+use game_of_life::rules::Rule;
+use game_of_life::tracking::{Tracker, Velocity, CONFIRMATION_GENERATIONS};
+
+const SIZE: usize = 96;
+
+const GLIDER: &str = "x = 3, y = 3\nbo$2bo$3o!";
+const LWSS: &str = "x = 5, y = 4\nbo2bo$o$o3bo$4o!";
+const BLOCK: &str = "x = 2, y = 2\n2o$2o!";
+const BLINKER: &str = "x = 3, y = 1\n3o!";
+const BEEHIVE: &str = "x = 4, y = 3\nb2o$o2bo$b2o!";
+
+fn board(objects: &[(i32, i32, &str)]) -> Cells {
+    let mut cells = core::init_cells(SIZE, SIZE, false);
+    for (x, y, rle) in objects {
+        let pattern = rle::parse_rle(rle).unwrap();
+        rle::place_pattern(&pattern, &mut cells, SIZE, SIZE, *x, *y);
+    }
+    cells
+}
+
+// Step and watch the board, returns the generations objects were confirmed at
+fn watch(tracker: &mut Tracker, mut cells: Cells, generations: i64) -> Vec<(i64, usize)> {
+    let rule = Rule::conway();
+    let mut confirmed = Vec::new();
+
+    for generation in 0..generations {
+        for index in tracker.observe(generation, &cells, SIZE, SIZE) {
+            confirmed.push((generation, index));
+        }
+        cells = core::step_cells(&cells, SIZE, SIZE, &rule);
+    }
+
+    confirmed
+}
+
+#[test]
+fn gliders_are_c4_diagonal() {
+    let mut tracker = Tracker::new();
+    let cells = board(&[(10, 10, GLIDER)]);
+
+    // The first time it looks the same is 4 generations in, then it needs
+    // to be seen moving for long enough before it's trusted
+    let first_sighting = 4;
+    let confirmed_at = first_sighting + CONFIRMATION_GENERATIONS as i64 - 1;
+
+    let confirmed = watch(&mut tracker, cells.clone(), confirmed_at);
+    assert!(confirmed.is_empty(), "{confirmed:?}");
+    assert!(tracker.objects.is_empty());
+
+    let mut tracker = Tracker::new();
+    let confirmed = watch(&mut tracker, cells, 60);
+    assert_eq!(confirmed, [(confirmed_at, 0)]);
+
+    let glider = &tracker.objects[0];
+    assert_eq!(
+        glider.velocity,
+        Velocity {
+            period: 4,
+            dx: 1,
+            dy: 1
+        }
+    );
+    assert_eq!(glider.population, 5);
+    assert_eq!(glider.first_seen, first_sighting);
+    assert_eq!(glider.last_seen, 59);
+    assert_eq!(
+        glider.velocity.to_string(),
+        "c/4 diagonal (moves 1,1 every 4 generations)"
+    );
+    assert_eq!(
+        tracker.summary(),
+        "1 moving object(s):\n       1 x c/4 diagonal (moves 1,1 every 4 generations)\n"
+    );
+}
+
+#[test]
+fn lightweight_spaceships_are_c2_orthogonal() {
+    let mut tracker = Tracker::new();
+    let cells = board(&[(80, 40, LWSS)]);
+
+    let confirmed = watch(&mut tracker, cells, 60);
+    assert_eq!(confirmed.len(), 1, "{confirmed:?}");
+    assert!(confirmed[0].0 >= CONFIRMATION_GENERATIONS as i64);
+
+    let lwss = &tracker.objects[0];
+    assert_eq!(
+        lwss.velocity,
+        Velocity {
+            period: 4,
+            dx: -2,
+            dy: 0
+        }
+    );
+    assert_eq!(lwss.velocity.speed(), "c/2");
+    assert!(lwss.velocity.is_orthogonal());
+    assert!(!lwss.velocity.is_diagonal());
+}
+
+#[test]
+fn still_lifes_and_oscillators_are_not_tracked() {
+    let mut tracker = Tracker::new();
+    let cells = board(&[
+        (10, 10, BLOCK),
+        (30, 10, BLINKER),
+        (50, 50, BEEHIVE),
+        (70, 20, BLOCK),
+    ]);
+
+    let confirmed = watch(&mut tracker, cells, 60);
+    assert!(confirmed.is_empty(), "{confirmed:?}");
+    assert!(tracker.objects.is_empty());
+    assert_eq!(tracker.active_objects(59).count(), 0);
+    assert_eq!(tracker.summary(), "0 moving object(s):\n");
+}
+
+#[test]
+fn a_glider_among_still_lifes_is_the_only_thing_moving() {
+    let mut tracker = Tracker::new();
+    let cells = board(&[(10, 10, GLIDER), (60, 10, BLOCK), (10, 60, BLINKER)]);
+
+    let confirmed = watch(&mut tracker, cells, 40);
+    assert_eq!(confirmed.len(), 1);
+    assert_eq!(tracker.objects[0].velocity.speed(), "c/4");
+    assert_eq!(tracker.active_objects(39).count(), 1);
+
+    // Gone from the board, so after a while not active any more
+    tracker.observe(60, &core::init_cells(SIZE, SIZE, false), SIZE, SIZE);
+    assert_eq!(tracker.active_objects(60).count(), 0);
+    assert_eq!(tracker.objects.len(), 1);
+}